@@ -1,17 +1,22 @@
+use crate::keys::hd;
 use crate::{
-    utils, BlsScalar, JubJubAffine, JubJubExtended, JubJubScalar, Nonce, PublicKey, ViewKey,
+    utils, BlsScalar, Error, JubJubAffine, JubJubExtended, JubJubScalar, Nonce, PublicKey, ViewKey,
 };
 
-use std::ptr;
+use std::ops::Mul;
 
-use rand::seq::SliceRandom;
+use jubjub::GENERATOR;
 
 use sodiumoxide::crypto::secretbox::{self, Key};
 
 pub mod merkle;
+pub mod nullifier_set;
+pub mod schnorr;
 
 pub use merkle::{MerkleProof, MerkleProofProvider, ARITY, TREE_HEIGHT};
+pub use nullifier_set::{NullifierLeaf, NullifierOpening, NullifierSet, NullifierSetProof};
 pub use poseidon252::sponge::sponge::sponge_hash;
+pub use schnorr::Signature;
 
 #[cfg(test)]
 mod tests;
@@ -33,36 +38,164 @@ lazy_static::lazy_static! {
     };
 }
 
-/// Perform a DHKE to create a shared secret
-pub fn dhke(sk: &JubJubScalar, pk: &JubJubExtended) -> Key {
+/// Context label of the key derivation of the note encryption
+const NOTE_ENCRYPTION_LABEL: &[u8] = b"phoenix-note-encryption";
+/// Context label of the hash of the one-time public key of a note
+const STEALTH_ADDRESS_LABEL: &[u8] = b"phoenix-stealth-address";
+/// Context label of the hash of an asset id to its value generator
+const ASSET_GENERATOR_LABEL: &[u8] = b"phoenix-asset-generator";
+
+/// Version of the key derivation used to encrypt the data of a note
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum KdfVersion {
+    /// The raw bytes of the `y` coordinate of the shared point are the key. Deprecated; supported
+    /// only to decrypt the notes created before [`KdfVersion::V1`]
+    Legacy,
+    /// The key is `Blake2b("phoenix-note-encryption", S, R)[0..32]`, where `S` is the compressed
+    /// shared point and `R` the compressed `r · G` of the note
+    V1,
+}
+
+impl KdfVersion {
+    /// Version used to encrypt new notes
+    pub const CURRENT: KdfVersion = KdfVersion::V1;
+
+    /// Versions attempted to decrypt a note, from the newest to the oldest
+    pub const ALL: [KdfVersion; 2] = [KdfVersion::V1, KdfVersion::Legacy];
+}
+
+/// Purpose of a poseidon hash
+///
+/// The tag of the domain is absorbed by the sponge before the inputs, so the hashes of different
+/// purposes never collide even if their inputs are equal. The tags are part of the consensus, and
+/// must never be reassigned.
+///
+/// The tags changed the note hashes, the nullifiers and the transaction hashes. A state created
+/// before they were introduced, such as a persisted [`crate::db::Db`], or nullifiers and merkle
+/// openings computed by an older node, is incompatible and must be rebuilt from the transactions.
+/// Only the stealth addresses of the older notes are still recognized, see
+/// [`hash_jubjub_projective_to_jubjub_scalar`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HashDomain {
+    /// [`crate::Note::hash`]
+    Note = 1,
+    /// [`crate::Note::generate_nullifier`]
+    Nullifier = 2,
+    /// [`crate::Transaction::hash`]
+    Transaction = 3,
+    /// [`hash_jubjub_projective`]
+    JubJubPoint = 4,
+    /// [`jubjub_scalar_to_bls`]
+    JubJubScalar = 5,
+    /// Leaves of the nullifier set tree
+    NullifierSetLeaf = 6,
+    /// Internal nodes of the nullifier set tree
+    NullifierSetNode = 7,
+    /// Root of the nullifier set, bound to the number of leaves
+    NullifierSetRoot = 8,
+    /// [`crate::Transaction::sign`]
+    TransactionSignature = 9,
+}
+
+impl HashDomain {
+    /// Field element absorbed before the inputs of the hash
+    pub fn tag(self) -> BlsScalar {
+        BlsScalar::from(self as u64)
+    }
+}
+
+/// Perform `H(tag, inputs)` with the tag of the provided domain
+pub fn domain_hash(domain: HashDomain, inputs: &[BlsScalar]) -> BlsScalar {
+    let mut tagged = Vec::with_capacity(inputs.len() + 1);
+
+    tagged.push(domain.tag());
+    tagged.extend_from_slice(inputs);
+
+    sponge_hash(&tagged)
+}
+
+/// Perform a DHKE to create a shared secret, and derive the symmetric key of the provided version
+///
+/// `R` is the public DHKE randomness of the note, bound to the key to separate the notes
+pub fn dhke(
+    sk: &JubJubScalar,
+    pk: &JubJubExtended,
+    R: &JubJubExtended,
+    version: KdfVersion,
+) -> Key {
     let shared_secret = JubJubAffine::from(pk * sk);
-    let shared_secret = shared_secret.get_y().0;
 
-    let mut key = [0x00u8; 32];
-    unsafe {
-        let src = (&shared_secret).as_ptr() as *const u8;
-        ptr::copy_nonoverlapping(src, key.as_mut_ptr(), 32);
+    let mut key = [0x00u8; secretbox::KEYBYTES];
+    match version {
+        KdfVersion::Legacy => {
+            shared_secret
+                .get_y()
+                .0
+                .iter()
+                .zip(key.chunks_mut(8))
+                .for_each(|(l, k)| k.copy_from_slice(&l.to_le_bytes()));
+        }
+
+        KdfVersion::V1 => {
+            let mut hash = hd::hash(
+                NOTE_ENCRYPTION_LABEL,
+                &[
+                    &shared_secret.to_bytes()[..],
+                    &JubJubAffine::from(R).to_bytes()[..],
+                ],
+            );
+
+            key.copy_from_slice(&hash[..secretbox::KEYBYTES]);
+            utils::wipe_bytes(&mut hash);
+        }
     }
 
     Key(key)
 }
 
 /// Encrypt a message using `r` as secret for the sender, and `pk` as public for the receiver
+///
+/// The key is derived with [`KdfVersion::CURRENT`]
 pub fn encrypt<V: AsRef<[u8]>>(
     r: &JubJubScalar,
     pk: &PublicKey,
     nonce: &Nonce,
     value: V,
 ) -> Vec<u8> {
-    secretbox::seal(value.as_ref(), nonce, &dhke(r, pk.A()))
+    let R = JubJubExtended::from(GENERATOR).mul(r);
+    let key = dhke(r, pk.A(), &R, KdfVersion::CURRENT);
+
+    secretbox::seal(value.as_ref(), nonce, &key)
 }
 
 /// Decrypt a message using `r_g` as public of the sender, and `vk` as secret for the receiver
-pub fn decrypt(R: &JubJubExtended, vk: &ViewKey, nonce: &Nonce, value: &[u8]) -> Vec<u8> {
-    secretbox::open(value, nonce, &dhke(vk.a(), R)).unwrap_or({
-        let mut value = value.to_vec();
-        value.shuffle(&mut rand::thread_rng());
-        value
+///
+/// Every [`KdfVersion`] is attempted, so the notes encrypted with a previous version are still
+/// decrypted. If the ciphertext can't be authenticated with any version,
+/// [`Error::DecryptionFailed`] is returned.
+pub fn decrypt(
+    R: &JubJubExtended,
+    vk: &ViewKey,
+    nonce: &Nonce,
+    value: &[u8],
+) -> Result<Vec<u8>, Error> {
+    decrypt_versioned(R, vk, nonce, value)
+        .map(|(value, _)| value)
+        .ok_or(Error::DecryptionFailed)
+}
+
+/// Decrypt a message, and return the [`KdfVersion`] it was encrypted with. Return `None` if the
+/// view key can't decrypt it with any version.
+pub fn decrypt_versioned(
+    R: &JubJubExtended,
+    vk: &ViewKey,
+    nonce: &Nonce,
+    value: &[u8],
+) -> Option<(Vec<u8>, KdfVersion)> {
+    KdfVersion::ALL.iter().find_map(|version| {
+        secretbox::open(value, nonce, &dhke(vk.a(), R, R, *version))
+            .ok()
+            .map(|v| (v, *version))
     })
 }
 
@@ -70,20 +203,85 @@ pub fn decrypt(R: &JubJubExtended, vk: &ViewKey, nonce: &Nonce, value: &[u8]) ->
 pub fn hash_jubjub_projective(p: &JubJubExtended) -> BlsScalar {
     let p = JubJubExtended::from(JubJubAffine::from(p));
 
+    domain_hash(
+        HashDomain::JubJubPoint,
+        &[p.get_x(), p.get_y(), p.get_z(), p.get_t1(), p.get_t2()],
+    )
+}
+
+/// Untagged `H(x, y, z, t)` of the deterministic representation of the projective point
+///
+/// This is the hash of the point before [`HashDomain`] was introduced. It must stay untagged, so
+/// the `PKr` of the notes derived with [`hash_jubjub_projective_to_jubjub_scalar`] is unchanged.
+fn legacy_hash_jubjub_projective(p: &JubJubExtended) -> BlsScalar {
+    let p = JubJubExtended::from(JubJubAffine::from(p));
+
     sponge_hash(&[p.get_x(), p.get_y(), p.get_z(), p.get_t1(), p.get_t2()])
 }
 
 /// Perform  a poseidon merkle slice hash strategy on a bits representation of a jubjub scalar
 pub fn jubjub_scalar_to_bls(s: &JubJubScalar) -> BlsScalar {
     let bits = utils::jubjub_scalar_to_bls_bits(s);
-    sponge_hash(&bits)
+    domain_hash(HashDomain::JubJubScalar, &bits)
+}
+
+/// Hash the point into a uniform [`JubJubScalar`]
+///
+/// The compressed point is hashed with Blake2b-512 under the `phoenix-stealth-address` domain,
+/// and the 512 bits are reduced modulo the order of the scalar field, so the bias of the result
+/// is negligible. This is the `H` of `PKr = H(a · R) · G + B`.
+pub fn hash_to_jubjub_scalar(p: &JubJubExtended) -> JubJubScalar {
+    let p = JubJubAffine::from(p).to_bytes();
+
+    JubJubScalar::from_bytes_wide(&hd::hash(STEALTH_ADDRESS_LABEL, &[&p[..]]))
+}
+
+/// Generator of the value commitments of an asset, `G_asset` of `value · G_asset + blinding · H`
+///
+/// The native asset keeps the jubjub generator, so its commitments are unchanged. Any other asset
+/// id is hashed with Blake2b under the `phoenix-asset-generator` domain, with a counter appended
+/// until the digest decodes to a point, and the point is cleared of its cofactor. No discrete log
+/// relation between the generators is known, so the value of an asset can't be committed as the
+/// value of another.
+pub fn asset_generator(asset: u64) -> JubJubExtended {
+    if asset == crate::NATIVE_ASSET {
+        return JubJubExtended::from(GENERATOR);
+    }
+
+    let mut counter = 0u32;
+    loop {
+        let hash = hd::hash(
+            ASSET_GENERATOR_LABEL,
+            &[&asset.to_le_bytes()[..], &counter.to_le_bytes()[..]],
+        );
+
+        let mut bytes = [0x00u8; utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE];
+        bytes.copy_from_slice(&hash[..utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE]);
+
+        if let Some(p) = Option::<JubJubAffine>::from(JubJubAffine::from_bytes(bytes)) {
+            let p = JubJubExtended::from(p).mul_by_cofactor();
+
+            if !bool::from(p.is_identity()) {
+                return p;
+            }
+        }
+
+        counter += 1;
+    }
 }
 
 /// Hash the point into a [`BlsScalar`], decompose the result in bits and reconstruct a
 /// [`JubJubScalar`] from the bits
+///
+/// The sponge output is a BLS scalar reinterpreted bit by bit as a JubJub scalar, so the result
+/// is not uniform. The notes created before [`hash_to_jubjub_scalar`] replaced this function still
+/// have their `PKr` derived with it; [`Note::is_owned_by`](crate::Note::is_owned_by),
+/// [`Note::sk_r`](crate::Note::sk_r) and
+/// [`Note::subaddress_index`](crate::Note::subaddress_index) fall back to it so these notes
+/// can be spent. New notes must never be created with it.
+#[deprecated(note = "biased; use `hash_to_jubjub_scalar`")]
 pub fn hash_jubjub_projective_to_jubjub_scalar(p: &JubJubExtended) -> JubJubScalar {
-    // TODO - Review and improve
-    let s = hash_jubjub_projective(p);
+    let s = legacy_hash_jubjub_projective(p);
 
     let two = JubJubScalar::from(2u64);
     let mut result = JubJubScalar::zero();
@@ -0,0 +1,312 @@
+use crate::crypto::{self, HashDomain};
+use crate::{BlsScalar, Error, Nullifier};
+
+use std::collections::BTreeMap;
+
+/// Leaf of the nullifier set, linked to the next nullifier in sorted order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NullifierLeaf {
+    nullifier: Nullifier,
+    next: Option<Nullifier>,
+}
+
+impl NullifierLeaf {
+    /// Nullifier of the leaf
+    pub fn nullifier(&self) -> &Nullifier {
+        &self.nullifier
+    }
+
+    /// Smallest nullifier of the set greater than the nullifier of the leaf, if any
+    pub fn next(&self) -> Option<&Nullifier> {
+        self.next.as_ref()
+    }
+
+    fn hash(&self) -> BlsScalar {
+        let next = self.next.map(|n| *n.s()).unwrap_or_else(BlsScalar::zero);
+
+        crypto::domain_hash(HashDomain::NullifierSetLeaf, &[*self.nullifier.s(), next])
+    }
+}
+
+/// Opening of a leaf of the nullifier set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NullifierOpening {
+    idx: u64,
+    leaf: NullifierLeaf,
+    path: Vec<BlsScalar>,
+}
+
+impl NullifierOpening {
+    /// Position of the leaf in the tree
+    pub fn idx(&self) -> u64 {
+        self.idx
+    }
+
+    /// Opened leaf
+    pub fn leaf(&self) -> &NullifierLeaf {
+        &self.leaf
+    }
+
+    /// Sibling hashes, from the leaf level up to the root
+    pub fn path(&self) -> &[BlsScalar] {
+        self.path.as_slice()
+    }
+
+    /// Reconstruct the nullifier set root from the opening, if it is consistent with the
+    /// provided number of leaves
+    fn root(&self, count: u64) -> Option<BlsScalar> {
+        if self.idx >= count || self.path.len() != tree_height(count) {
+            return None;
+        }
+
+        let mut idx = self.idx;
+        let hash = self.path.iter().fold(self.leaf.hash(), |hash, sibling| {
+            let parent = if idx & 1 == 0 {
+                crypto::domain_hash(HashDomain::NullifierSetNode, &[hash, *sibling])
+            } else {
+                crypto::domain_hash(HashDomain::NullifierSetNode, &[*sibling, hash])
+            };
+
+            idx >>= 1;
+            parent
+        });
+
+        Some(set_root(count, hash))
+    }
+}
+
+/// Proof of membership, or non-membership, of a nullifier in the nullifier set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NullifierSetProof {
+    /// The nullifier is the opened leaf
+    Inclusion {
+        count: u64,
+        opening: NullifierOpening,
+    },
+    /// The nullifier is not in the set. The opened leaf is its predecessor in sorted order, and
+    /// links to a greater nullifier, or to none.
+    Exclusion { count: u64, lower: NullifierOpening },
+}
+
+impl NullifierSetProof {
+    /// Verify the proof against a nullifier set root, without access to the set.
+    ///
+    /// Return `true` if the nullifier is proven to be in the set, `false` if it is proven not to
+    /// be, and [`Error::InvalidProof`] if the proof is inconsistent with the root.
+    pub fn verify(&self, root: &BlsScalar, nullifier: &Nullifier) -> Result<bool, Error> {
+        let valid = |o: &NullifierOpening, count| o.root(count).map(|r| &r == root) == Some(true);
+
+        match self {
+            NullifierSetProof::Inclusion { count, opening } => {
+                // The sentinel leaf is not a member of the set
+                if opening.idx > 0
+                    && opening.leaf.nullifier() == nullifier
+                    && valid(opening, *count)
+                {
+                    Ok(true)
+                } else {
+                    Err(Error::InvalidProof)
+                }
+            }
+
+            NullifierSetProof::Exclusion { count, lower } => {
+                let surrounds = lower.leaf.nullifier() < nullifier
+                    && lower.leaf.next().map(|n| nullifier < n).unwrap_or(true);
+
+                if surrounds && valid(lower, *count) {
+                    Ok(false)
+                } else {
+                    Err(Error::InvalidProof)
+                }
+            }
+        }
+    }
+}
+
+/// Set of the spent nullifiers, committed by a binary poseidon tree
+///
+/// The leaves are appended in insertion order, and every leaf is linked to the next nullifier in
+/// sorted order, so an insertion updates two leaves, and the absence of a nullifier is proven by
+/// the opening of its predecessor. The first leaf is a sentinel with the zero nullifier, which is
+/// reserved. The tree is padded with zeroed leaves to the next power of two, and its root is
+/// defined as `H(count, tree root)`, where `count` is the number of leaves.
+///
+/// The levels of the tree are cached, so the root is immediate, and an insertion or a proof is
+/// logarithmic in the size of the set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NullifierSet {
+    positions: BTreeMap<Nullifier, u64>,
+    leaves: Vec<NullifierLeaf>,
+    levels: Vec<Vec<BlsScalar>>,
+}
+
+impl Default for NullifierSet {
+    fn default() -> Self {
+        let sentinel = NullifierLeaf {
+            nullifier: Nullifier::default(),
+            next: None,
+        };
+
+        let mut positions = BTreeMap::new();
+        positions.insert(sentinel.nullifier, 0);
+
+        NullifierSet {
+            positions,
+            leaves: vec![sentinel],
+            levels: vec![vec![sentinel.hash()]],
+        }
+    }
+}
+
+impl NullifierSet {
+    /// Number of nullifiers of the set
+    pub fn len(&self) -> usize {
+        self.leaves.len() - 1
+    }
+
+    /// Return `true` if no nullifier was inserted
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate the nullifiers of the set, in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = &Nullifier> {
+        self.leaves.iter().skip(1).map(|l| l.nullifier())
+    }
+
+    /// Insert a nullifier. Return `false` if it was already in the set.
+    ///
+    /// The zero nullifier is reserved for the sentinel leaf, and is rejected with
+    /// [`Error::InvalidParameters`].
+    pub fn insert(&mut self, nullifier: Nullifier) -> Result<bool, Error> {
+        if nullifier == Nullifier::default() {
+            return Err(Error::InvalidParameters);
+        }
+
+        if self.positions.contains_key(&nullifier) {
+            return Ok(false);
+        }
+
+        let lower = self.lower(&nullifier);
+        let idx = self.leaves.len();
+
+        self.leaves.push(NullifierLeaf {
+            nullifier,
+            next: self.leaves[lower].next,
+        });
+        self.leaves[lower].next = Some(nullifier);
+        self.positions.insert(nullifier, idx as u64);
+
+        if idx < self.levels[0].len() {
+            self.update(lower);
+            self.update(idx);
+        } else {
+            self.grow();
+        }
+
+        Ok(true)
+    }
+
+    /// Return the root of the set
+    pub fn root(&self) -> BlsScalar {
+        set_root(
+            self.leaves.len() as u64,
+            self.levels[self.levels.len() - 1][0],
+        )
+    }
+
+    /// Create a proof that the provided nullifier is, or is not, in the set
+    pub fn proof(&self, nullifier: &Nullifier) -> NullifierSetProof {
+        let count = self.leaves.len() as u64;
+
+        match self.positions.get(nullifier) {
+            Some(idx) if *idx > 0 => NullifierSetProof::Inclusion {
+                count,
+                opening: self.opening(*idx as usize),
+            },
+
+            _ => NullifierSetProof::Exclusion {
+                count,
+                lower: self.opening(self.lower(nullifier)),
+            },
+        }
+    }
+
+    /// Position of the greatest leaf below the provided nullifier. The sentinel is returned for
+    /// the zero nullifier.
+    fn lower(&self, nullifier: &Nullifier) -> usize {
+        self.positions
+            .range(..nullifier)
+            .next_back()
+            .map(|(_, idx)| *idx as usize)
+            .unwrap_or(0)
+    }
+
+    fn opening(&self, idx: usize) -> NullifierOpening {
+        let mut i = idx;
+        let path = self.levels[..self.levels.len() - 1]
+            .iter()
+            .map(|l| {
+                let sibling = l[i ^ 1];
+                i >>= 1;
+                sibling
+            })
+            .collect();
+
+        NullifierOpening {
+            idx: idx as u64,
+            leaf: self.leaves[idx],
+            path,
+        }
+    }
+
+    /// Rehash the leaf on the provided position, and its path up to the tree root
+    fn update(&mut self, idx: usize) {
+        let mut i = idx;
+        self.levels[0][i] = self.leaves[i].hash();
+
+        for l in 1..self.levels.len() {
+            i >>= 1;
+            self.levels[l][i] = crypto::domain_hash(
+                HashDomain::NullifierSetNode,
+                &[self.levels[l - 1][2 * i], self.levels[l - 1][2 * i + 1]],
+            );
+        }
+    }
+
+    /// Double the width of the tree, and rebuild its levels. The cost is amortized by the
+    /// insertions that fill the new leaves.
+    fn grow(&mut self) {
+        let width = 1usize << tree_height(self.leaves.len() as u64);
+        let mut level: Vec<BlsScalar> = self.leaves.iter().map(|l| l.hash()).collect();
+        level.resize(width, BlsScalar::zero());
+
+        let mut levels = vec![level];
+        while levels[levels.len() - 1].len() > 1 {
+            let parent = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|c| crypto::domain_hash(HashDomain::NullifierSetNode, &[c[0], c[1]]))
+                .collect();
+
+            levels.push(parent);
+        }
+
+        self.levels = levels;
+    }
+}
+
+fn set_root(count: u64, tree_root: BlsScalar) -> BlsScalar {
+    crypto::domain_hash(
+        HashDomain::NullifierSetRoot,
+        &[BlsScalar::from(count), tree_root],
+    )
+}
+
+fn tree_height(count: u64) -> usize {
+    let mut height = 0;
+    while (1u64 << height) < count {
+        height += 1;
+    }
+
+    height
+}
@@ -0,0 +1,105 @@
+use crate::keys::hd;
+use crate::{utils, Error, JubJubAffine, JubJubExtended, JubJubScalar};
+
+use std::ops::Mul;
+
+use jubjub::GENERATOR;
+use rand::RngCore;
+
+const NONCE_LABEL: &[u8] = b"phoenix-schnorr-nonce";
+const CHALLENGE_LABEL: &[u8] = b"phoenix-schnorr-challenge";
+
+/// Serialized size of a [`Signature`]
+pub const SIGNATURE_SIZE: usize =
+    utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE + utils::JUBJUB_SCALAR_SERIALIZED_SIZE;
+
+/// Schnorr signature over JubJub
+///
+/// For a secret `x` with public `X = x · G`, the signature of `m` is `(R, s)`, where
+/// `R = k · G`, `c = H(R, X, m)` and `s = k + c · x`. It is valid if `s · G == R + c · X`.
+///
+/// The nonce `k` is derived from the secret, the message and fresh randomness, so a faulty random
+/// number generator doesn't leak the secret. Both hashes are Blake2b-512 reduced modulo the JubJub
+/// scalar field order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signature {
+    R: JubJubExtended,
+    s: JubJubScalar,
+}
+
+impl Signature {
+    /// Sign a message with the provided secret
+    pub fn sign(secret: &JubJubScalar, msg: &[u8]) -> Self {
+        let mut entropy = [0x00u8; 32];
+        rand::thread_rng().fill_bytes(&mut entropy);
+
+        let k = hash(NONCE_LABEL, &[&secret.to_bytes()[..], &entropy[..], msg]);
+        utils::wipe_bytes(&mut entropy);
+
+        let R = JubJubExtended::from(GENERATOR).mul(&k);
+        let X = JubJubExtended::from(GENERATOR).mul(secret);
+
+        let s = k + challenge(&R, &X, msg) * secret;
+
+        Signature { R, s }
+    }
+
+    /// Verify the signature of a message against the public point of the signer
+    pub fn verify(&self, public: &JubJubExtended, msg: &[u8]) -> bool {
+        let c = challenge(&self.R, public, msg);
+
+        JubJubExtended::from(GENERATOR).mul(&self.s) == self.R + public.mul(&c)
+    }
+
+    /// Return the commitment point `R`
+    pub fn R(&self) -> &JubJubExtended {
+        &self.R
+    }
+
+    /// Return the response scalar `s`
+    pub fn s(&self) -> &JubJubScalar {
+        &self.s
+    }
+
+    /// Serialize the signature as the compressed `R` followed by `s`
+    pub fn to_bytes(&self) -> [u8; SIGNATURE_SIZE] {
+        let mut bytes = [0x00u8; SIGNATURE_SIZE];
+
+        bytes[0..utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE]
+            .copy_from_slice(&JubJubAffine::from(self.R).to_bytes()[..]);
+        bytes[utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE..].copy_from_slice(&self.s.to_bytes()[..]);
+
+        bytes
+    }
+
+    /// Deserialize a signature created with [`Signature::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != SIGNATURE_SIZE {
+            return Err(Error::InvalidParameters);
+        }
+
+        let R = utils::deserialize_compressed_jubjub(
+            &bytes[0..utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE],
+        )
+        .map_err(|_| Error::InvalidPoint)?;
+        let s =
+            utils::deserialize_jubjub_scalar(&bytes[utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE..])?;
+
+        Ok(Signature { R, s })
+    }
+}
+
+fn challenge(R: &JubJubExtended, X: &JubJubExtended, msg: &[u8]) -> JubJubScalar {
+    hash(
+        CHALLENGE_LABEL,
+        &[
+            &JubJubAffine::from(R).to_bytes()[..],
+            &JubJubAffine::from(X).to_bytes()[..],
+            msg,
+        ],
+    )
+}
+
+fn hash(label: &[u8], inputs: &[&[u8]]) -> JubJubScalar {
+    JubJubScalar::from_bytes_wide(&hd::hash(label, inputs))
+}
@@ -1,4 +1,9 @@
-use crate::{crypto, utils, Note, NoteGenerator, ObfuscatedNote, SecretKey};
+use crate::{
+    crypto, utils, BlsScalar, Error, JubJubScalar, Note, NoteGenerator, Nullifier, ObfuscatedNote,
+    SecretKey,
+};
+
+use sodiumoxide::crypto::secretbox;
 
 #[test]
 fn decrypt() {
@@ -11,7 +16,7 @@ fn decrypt() {
 
     let bytes = b"some data";
     let encrypted = crypto::encrypt(&r, &pk, &nonce, bytes);
-    let decrypted = crypto::decrypt(&R, &vk, &nonce, encrypted.as_slice());
+    let decrypted = crypto::decrypt(&R, &vk, &nonce, encrypted.as_slice()).unwrap();
 
     assert_eq!(&bytes[..], decrypted.as_slice());
 }
@@ -30,9 +35,10 @@ fn decrypt_with_wrong_key_should_fail() {
 
     let bytes = b"some data";
     let encrypted = crypto::encrypt(&r, &pk, &nonce, bytes);
-    let decrypted = crypto::decrypt(&r_g, &wrong_vk, &nonce, encrypted.as_slice());
-
-    assert_ne!(&bytes[..], decrypted.as_slice());
+    match crypto::decrypt(&r_g, &wrong_vk, &nonce, encrypted.as_slice()) {
+        Err(Error::DecryptionFailed) => (),
+        _ => panic!("The decryption with the wrong key must fail"),
+    }
 }
 
 #[test]
@@ -44,7 +50,143 @@ fn decrypt_obfuscated_note() {
     let vk = sk.view_key();
 
     let (note, _) = ObfuscatedNote::output(&pk, value);
-    let decrypt_value = note.value(Some(&vk));
+    let decrypt_value = note.value(Some(&vk)).unwrap();
 
     assert_eq!(decrypt_value, value);
 }
+
+#[test]
+fn nullifier_set_proofs() {
+    let mut rng = rand::thread_rng();
+
+    let nullifiers: Vec<Nullifier> = (0..5).map(|_| BlsScalar::random(&mut rng).into()).collect();
+
+    let empty = crypto::NullifierSet::default();
+    let n: Nullifier = BlsScalar::random(&mut rng).into();
+    assert!(!empty.proof(&n).verify(&empty.root(), &n).unwrap());
+
+    let mut set = crypto::NullifierSet::default();
+    for n in nullifiers.iter() {
+        assert!(set.insert(*n).unwrap());
+    }
+    assert!(!set.insert(nullifiers[0]).unwrap());
+    assert!(set.insert(Nullifier::default()).is_err());
+    assert_eq!(nullifiers.len(), set.len());
+
+    let root = set.root();
+
+    for n in nullifiers.iter() {
+        assert!(set.proof(n).verify(&root, n).unwrap());
+    }
+
+    for _ in 0..16 {
+        let n: Nullifier = BlsScalar::random(&mut rng).into();
+        assert!(!set.proof(&n).verify(&root, &n).unwrap());
+    }
+
+    let proof = set.proof(&nullifiers[0]);
+    assert!(proof.verify(&root, &nullifiers[1]).is_err());
+
+    let mut smaller = crypto::NullifierSet::default();
+    nullifiers[1..].iter().for_each(|n| {
+        smaller.insert(*n).unwrap();
+    });
+    assert!(proof.verify(&smaller.root(), &nullifiers[0]).is_err());
+
+    // The exclusion proof of a spent nullifier can't be forged from a stale state
+    let stale = smaller.proof(&nullifiers[0]);
+    assert!(stale.verify(&root, &nullifiers[0]).is_err());
+}
+
+#[test]
+fn schnorr_signature() {
+    let sk = SecretKey::default();
+    let pk = sk.public_key();
+    let msg = b"withdraw bid";
+
+    let signature = sk.sign(&msg[..]);
+    assert!(pk.verify(&msg[..], &signature));
+    assert!(!pk.verify(&b"withdraw stake"[..], &signature));
+    assert!(!SecretKey::default()
+        .public_key()
+        .verify(&msg[..], &signature));
+
+    let bytes = signature.to_bytes();
+    let deserialized = crypto::Signature::from_bytes(&bytes[..]).unwrap();
+    assert_eq!(signature, deserialized);
+    assert!(pk.verify(&msg[..], &deserialized));
+
+    let mut tampered = bytes;
+    tampered[40] ^= 0x01;
+    match crypto::Signature::from_bytes(&tampered[..]) {
+        Ok(s) => assert!(!pk.verify(&msg[..], &s)),
+        Err(_) => (),
+    }
+    assert!(crypto::Signature::from_bytes(&bytes[1..]).is_err());
+}
+
+#[test]
+fn schnorr_note_signature() {
+    let sk = SecretKey::default();
+    let (note, _) = ObfuscatedNote::output(&sk.public_key(), 10);
+    let msg = b"note owner";
+
+    let signature = note.sign(&sk, &msg[..]);
+    assert!(note.verify_signature(&msg[..], &signature));
+
+    let wrong = note.sign(&SecretKey::default(), &msg[..]);
+    assert!(!note.verify_signature(&msg[..], &wrong));
+
+    // The signature is bound to the one-time key of the note
+    let (other, _) = ObfuscatedNote::output(&sk.public_key(), 10);
+    assert!(!other.verify_signature(&msg[..], &signature));
+
+    let forged = crypto::Signature::sign(&JubJubScalar::one(), &msg[..]);
+    assert!(!note.verify_signature(&msg[..], &forged));
+}
+
+#[test]
+fn decrypt_legacy_kdf() {
+    let sk = SecretKey::default();
+    let pk = sk.public_key();
+    let vk = sk.view_key();
+    let nonce = utils::gen_nonce();
+
+    let (r, R, _) = ObfuscatedNote::generate_pk_r(&pk);
+    let bytes = b"some data";
+
+    let legacy = crypto::dhke(&r, pk.A(), &R, crypto::KdfVersion::Legacy);
+    let current = crypto::dhke(&r, pk.A(), &R, crypto::KdfVersion::CURRENT);
+    assert_ne!(legacy, current);
+
+    // The receiver derives the same keys
+    assert_eq!(
+        legacy,
+        crypto::dhke(vk.a(), &R, &R, crypto::KdfVersion::Legacy)
+    );
+    assert_eq!(
+        current,
+        crypto::dhke(vk.a(), &R, &R, crypto::KdfVersion::CURRENT)
+    );
+
+    let encrypted = secretbox::seal(&bytes[..], &nonce, &legacy);
+    let (decrypted, version) =
+        crypto::decrypt_versioned(&R, &vk, &nonce, encrypted.as_slice()).unwrap();
+    assert_eq!(&bytes[..], decrypted.as_slice());
+    assert_eq!(crypto::KdfVersion::Legacy, version);
+    assert_eq!(
+        &bytes[..],
+        crypto::decrypt(&R, &vk, &nonce, encrypted.as_slice())
+            .unwrap()
+            .as_slice()
+    );
+
+    let encrypted = crypto::encrypt(&r, &pk, &nonce, bytes);
+    let (decrypted, version) =
+        crypto::decrypt_versioned(&R, &vk, &nonce, encrypted.as_slice()).unwrap();
+    assert_eq!(&bytes[..], decrypted.as_slice());
+    assert_eq!(crypto::KdfVersion::V1, version);
+
+    let wrong_vk = SecretKey::default().view_key();
+    assert!(crypto::decrypt_versioned(&R, &wrong_vk, &nonce, encrypted.as_slice()).is_none());
+}
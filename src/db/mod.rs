@@ -1,9 +1,12 @@
 use crate::{
-    crypto, BlsScalar, Error, MerkleProofProvider, Note, NoteVariant, Nullifier, Transaction,
-    TransactionItem, MAX_NOTES_PER_TRANSACTION,
+    crypto, utils, BlsScalar, Error, MerkleProofProvider, Note, NoteVariant, Nullifier,
+    Transaction, TransactionItem, MAX_NOTES_PER_TRANSACTION,
 };
 
-use std::io;
+use std::collections::BTreeSet;
+use std::io::{self, Read, Write};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
 
 use bytehash::ByteHash;
 use kelvin::annotations::Count;
@@ -12,6 +15,12 @@ use kelvin_hamt::CountingHAMTMap as HAMTMap;
 use kelvin_radix::DefaultRadixMap as RadixMap;
 use tracing::trace;
 
+/// Version of the persisted format of the [`Db`]
+///
+/// The format before the first version was not tagged, and is incompatible: its note hashes and
+/// nullifiers predate [`crypto::HashDomain`].
+const DB_VERSION: u8 = 1;
+
 /// Type used for notes storage
 pub type NotesDb = Db<Blake2b>;
 /// Type used for notes iterator
@@ -20,11 +29,21 @@ pub type NotesIter = DbNotesIterator<Blake2b>;
 #[cfg(test)]
 mod tests;
 
+/// State change emitted by a [`Db`] to its subscribers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DbEvent {
+    /// A note was appended to the tree on the provided position
+    Note(u64, NoteVariant),
+    /// A nullifier was inserted to the set
+    Nullifier(Nullifier),
+}
+
 /// Database structure for the notes and nullifiers storage
-#[derive(Clone)]
 pub struct Db<H: ByteHash> {
     notes: HAMTMap<u64, NoteVariant, H>,
     nullifiers: RadixMap<Nullifier, (), H>,
+    nullifier_set: crypto::NullifierSet,
+    subscribers: Mutex<Vec<Sender<DbEvent>>>,
 }
 
 impl<H: ByteHash> Default for Db<H> {
@@ -32,20 +51,69 @@ impl<H: ByteHash> Default for Db<H> {
         Db {
             notes: HAMTMap::default(),
             nullifiers: RadixMap::default(),
+            nullifier_set: crypto::NullifierSet::default(),
+            subscribers: Mutex::new(vec![]),
         }
     }
 }
 
+impl<H: ByteHash> Clone for Db<H> {
+    /// The subscriptions are bound to the instance, so the events of a mutated clone are not
+    /// propagated
+    fn clone(&self) -> Self {
+        Db {
+            notes: self.notes.clone(),
+            nullifiers: self.nullifiers.clone(),
+            nullifier_set: self.nullifier_set.clone(),
+            subscribers: Mutex::new(vec![]),
+        }
+    }
+}
+
+/// The persisted format starts with [`DB_VERSION`]. The nullifier set is persisted in insertion
+/// order, and its tree is rebuilt on restore.
 impl<H: ByteHash> Content<H> for Db<H> {
     fn persist(&mut self, sink: &mut Sink<H>) -> io::Result<()> {
+        sink.write_all(&[DB_VERSION])?;
+
         self.notes.persist(sink)?;
-        self.nullifiers.persist(sink)
+        self.nullifiers.persist(sink)?;
+
+        (self.nullifier_set.len() as u64).persist(sink)?;
+        for n in self.nullifier_set.iter() {
+            sink.write_all(&n.to_bytes())?;
+        }
+
+        Ok(())
     }
 
     fn restore(source: &mut Source<H>) -> io::Result<Self> {
+        let mut version = [0x00u8; 1];
+        source.read_exact(&mut version)?;
+        if version[0] != DB_VERSION {
+            return Err(Error::UnsupportedVersion(version[0]).into());
+        }
+
+        let notes = HAMTMap::restore(source)?;
+        let nullifiers = RadixMap::restore(source)?;
+
+        let count = u64::restore(source)?;
+        let mut nullifier_set = crypto::NullifierSet::default();
+        for _ in 0..count {
+            let n = utils::kelvin_source_to_bls_scalar(source).map(Nullifier::from)?;
+
+            match nullifier_set.insert(n) {
+                Ok(true) => (),
+                Ok(false) => return Err(Error::InvalidParameters.into()),
+                Err(e) => return Err(e.into()),
+            }
+        }
+
         Ok(Db {
-            notes: HAMTMap::restore(source)?,
-            nullifiers: RadixMap::restore(source)?,
+            notes,
+            nullifiers,
+            nullifier_set,
+            subscribers: Mutex::new(vec![]),
         })
     }
 }
@@ -85,8 +153,9 @@ pub fn merkle_opening(
 pub fn store(
     state: &mut Db<Blake2b>,
     transaction: &Transaction,
+    height: u64,
 ) -> Result<[Option<u64>; MAX_NOTES_PER_TRANSACTION], Error> {
-    let v = state.store_transaction(transaction)?;
+    let v = state.store_transaction(transaction, height)?;
 
     Ok(v)
 }
@@ -96,29 +165,54 @@ impl<H: ByteHash> Db<H> {
         Default::default()
     }
 
+    /// Store a [`Transaction`] included at the provided block height. Return the positions of the
+    /// fee and output notes.
+    ///
+    /// Every input is validated before any nullifier is inserted. The transaction is rejected with
+    /// [`Error::NoteLocked`] if one of its inputs declares an unlock height above `height`, and
+    /// with [`Error::DoubleSpending`] if a nullifier is already spent, or repeated in the
+    /// transaction.
+    ///
+    /// The declared unlock height is bound to the spent note only by the public input of
+    /// [`crate::zk::gadgets::input_preimage`]. The transaction circuit of [`crate::zk::prove`] is
+    /// still a mock without the gadget, so a spender that declares a lower unlock height is not
+    /// detected yet.
+    ///
+    /// The subscribers are notified only after the whole transaction is stored.
     pub fn store_transaction(
         &mut self,
         transaction: &Transaction,
+        height: u64,
     ) -> Result<[Option<u64>; MAX_NOTES_PER_TRANSACTION], Error> {
-        transaction
-            .inputs()
-            .iter()
-            .try_fold::<_, _, Result<_, Error>>(None, |_, i| {
-                let n = *i.nullifier();
+        let mut nullifiers = BTreeSet::new();
 
-                self.fetch_nullifier(&n)?
-                    .map(|_| Err(Error::DoubleSpending))
-                    .unwrap_or(Ok(()))?;
+        for i in transaction.inputs() {
+            if i.unlock_height > height {
+                return Err(Error::NoteLocked);
+            }
 
-                self.nullifiers.insert(n, ()).map_err(|e| e.into())
-            })?;
+            let n = *i.nullifier();
+            if !nullifiers.insert(n) || self.fetch_nullifier(&n)?.is_some() {
+                return Err(Error::DoubleSpending);
+            }
+        }
+
+        let mut events = vec![];
+
+        for i in transaction.inputs() {
+            let n = *i.nullifier();
+
+            self.insert_nullifier(n)?;
+            events.push(DbEvent::Nullifier(n));
+        }
 
         let mut idx = [None; MAX_NOTES_PER_TRANSACTION];
 
         let mut idx_iter = idx.iter_mut();
 
         let fee = transaction.fee();
-        let fee = self.store_unspent_note(fee.note().clone())?;
+        let (fee, note) = self.append_note(fee.note().clone())?;
+        events.push(DbEvent::Note(fee, note));
         idx_iter.next().map(|i| i.replace(fee));
 
         transaction
@@ -126,26 +220,31 @@ impl<H: ByteHash> Db<H> {
             .iter()
             .zip(idx_iter)
             .map(|(o, i)| {
-                self.store_unspent_note(o.note().clone())
-                    .map(|idx| i.replace(idx))?;
+                let (idx, note) = self.append_note(o.note().clone())?;
+                events.push(DbEvent::Note(idx, note));
+                i.replace(idx);
 
                 Ok(())
             })
             .collect::<Result<_, Error>>()?;
 
+        events.into_iter().for_each(|e| self.notify(e));
+
         Ok(idx)
     }
 
-    /// Store a set of [`Transaction`]. Return a set of positions of the included notes.
+    /// Store a set of [`Transaction`] included at the provided block height. Return a set of
+    /// positions of the included notes.
     pub fn store_bulk_transactions(
         &mut self,
         transactions: &[Transaction],
+        height: u64,
     ) -> Result<Vec<u64>, Error> {
         let mut idx = vec![];
 
         for t in transactions {
             trace!("Storing tx {}", t);
-            self.store_transaction(t)?
+            self.store_transaction(t, height)?
                 .iter()
                 .filter_map(|i| i.as_ref())
                 .for_each(|i| idx.push(*i));
@@ -155,13 +254,22 @@ impl<H: ByteHash> Db<H> {
     }
 
     /// Store a note. Return the position of the stored note on the tree.
-    pub fn store_unspent_note(&mut self, mut note: NoteVariant) -> Result<u64, Error> {
+    pub fn store_unspent_note(&mut self, note: NoteVariant) -> Result<u64, Error> {
+        let (idx, note) = self.append_note(note)?;
+        self.notify(DbEvent::Note(idx, note));
+
+        Ok(idx)
+    }
+
+    /// Append a note to the tree, without notifying the subscribers. Return its position and the
+    /// stored note.
+    fn append_note(&mut self, mut note: NoteVariant) -> Result<(u64, NoteVariant), Error> {
         let idx = self.notes.count() as u64;
 
         note.set_idx(idx.clone());
         self.notes.insert(idx.clone(), note)?;
 
-        Ok(idx)
+        Ok((idx, note))
     }
 
     /// Provided a position, return a strong typed note from the database
@@ -172,6 +280,70 @@ impl<H: ByteHash> Db<H> {
             .ok_or(Error::NotFound)
     }
 
+    fn insert_nullifier(&mut self, nullifier: Nullifier) -> Result<(), Error> {
+        self.nullifiers.insert(nullifier, ())?;
+
+        self.nullifier_set.insert(nullifier)?;
+
+        Ok(())
+    }
+
+    /// Subscribe to the notes and nullifiers stored from now on
+    pub fn subscribe(&self) -> Receiver<DbEvent> {
+        let (sender, receiver) = mpsc::channel();
+
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(sender);
+
+        receiver
+    }
+
+    /// Subscribe to the state changes, starting from the note on the provided position.
+    ///
+    /// The notes already stored from `idx` onwards are sent first, then every nullifier already
+    /// spent, in insertion order, followed by the live events. The nullifiers are not bound to a
+    /// note position, so all of them are replayed, including the spends of notes below `idx`.
+    pub fn subscribe_from(&self, idx: u64) -> Result<Receiver<DbEvent>, Error> {
+        let (sender, receiver) = mpsc::channel();
+
+        // The state can't be mutated while it is borrowed, so no event is lost between the
+        // replay and the subscription
+        for i in idx..self.notes.count() as u64 {
+            let _ = sender.send(DbEvent::Note(i, self.fetch_note(i)?));
+        }
+
+        for n in self.nullifier_set.iter() {
+            let _ = sender.send(DbEvent::Nullifier(*n));
+        }
+
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(sender);
+
+        Ok(receiver)
+    }
+
+    fn notify(&self, event: DbEvent) {
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|s| s.send(event).is_ok());
+    }
+
+    /// Return the root of the nullifier set, to be published so clients can verify a
+    /// [`crypto::NullifierSetProof`] without the state
+    pub fn nullifier_root(&self) -> BlsScalar {
+        self.nullifier_set.root()
+    }
+
+    /// Create a proof that the provided nullifier is, or is not, in the nullifier set
+    pub fn nullifier_proof(&self, nullifier: &Nullifier) -> crypto::NullifierSetProof {
+        self.nullifier_set.proof(nullifier)
+    }
+
     /// Verify the existence of a provided nullifier on the set
     pub fn fetch_nullifier(&self, nullifier: &Nullifier) -> Result<Option<()>, Error> {
         self.nullifiers
@@ -1,6 +1,6 @@
 use crate::{
-    db, MerkleProofProvider, Note, NoteGenerator, NoteVariant, ObfuscatedNote, SecretKey,
-    Transaction, TransparentNote,
+    db, DbEvent, Error, MerkleProofProvider, Note, NoteGenerator, NoteVariant, ObfuscatedNote,
+    SecretKey, Transaction, TransparentNote,
 };
 
 use kelvin::Blake2b;
@@ -28,7 +28,7 @@ fn transparent_note_serialization() {
 
     assert_eq!(note, db_note);
     assert!(db_note.is_owned_by(&vk));
-    assert_eq!(value, db_note.value(Some(&vk)));
+    assert_eq!(value, db_note.value(Some(&vk)).unwrap());
     assert_eq!(blinding_factor, db_note.blinding_factor(Some(&vk)).unwrap());
 }
 
@@ -55,10 +55,125 @@ fn obfuscated_note_serialization() {
 
     assert_eq!(note, db_note);
     assert!(db_note.is_owned_by(&vk));
-    assert_eq!(value, db_note.value(Some(&vk)));
+    assert_eq!(value, db_note.value(Some(&vk)).unwrap());
     assert_eq!(blinding_factor, db_note.blinding_factor(Some(&vk)).unwrap());
 }
 
+#[test]
+fn nullifier_set_membership() {
+    let mut db = db::Db::<Blake2b>::default();
+
+    let mut spent = vec![];
+    for _ in 0..3 {
+        let sk = SecretKey::default();
+        let note: NoteVariant = TransparentNote::output(&sk.public_key(), 10).0.into();
+        db.store_unspent_note(note).unwrap();
+
+        let merkle_opening = db.opening(&note).unwrap();
+        let input = note.to_transaction_input(merkle_opening, sk).unwrap();
+        spent.push(*input.nullifier());
+
+        let mut tx = Transaction::default();
+        tx.push_input(input).unwrap();
+        db.store_transaction(&tx, 0).unwrap();
+    }
+
+    let root = db.nullifier_root();
+
+    for n in spent.iter() {
+        assert!(db.nullifier_proof(n).verify(&root, n).unwrap());
+    }
+
+    let sk = SecretKey::default();
+    let note = TransparentNote::output(&sk.public_key(), 10).0;
+    let unspent = note.generate_nullifier(&sk);
+    assert!(!db
+        .nullifier_proof(&unspent)
+        .verify(&root, &unspent)
+        .unwrap());
+}
+
+#[test]
+fn state_subscription() {
+    let mut db = db::Db::<Blake2b>::default();
+
+    let sk = SecretKey::default();
+    let pk = sk.public_key();
+
+    let stored: Vec<NoteVariant> = (0..3)
+        .map(|_| {
+            let note: NoteVariant = TransparentNote::output(&pk, 10).0.into();
+            let idx = db.store_unspent_note(note).unwrap();
+            db.fetch_note(idx).unwrap()
+        })
+        .collect();
+
+    let live = db.subscribe();
+    let resumed = db.subscribe_from(1).unwrap();
+
+    let note: NoteVariant = ObfuscatedNote::output(&pk, 10).0.into();
+    let idx = db.store_unspent_note(note).unwrap();
+    let note = db.fetch_note(idx).unwrap();
+
+    let merkle_opening = db.opening(&note).unwrap();
+    let input = note.to_transaction_input(merkle_opening, sk).unwrap();
+    let nullifier = *input.nullifier();
+    let mut tx = Transaction::default();
+    tx.push_input(input).unwrap();
+    db.store_transaction(&tx, 0).unwrap();
+
+    let events: Vec<DbEvent> = live.try_iter().collect();
+    assert_eq!(DbEvent::Note(3, note), events[0]);
+    assert_eq!(DbEvent::Nullifier(nullifier), events[1]);
+    match events[2] {
+        DbEvent::Note(4, _) => (),
+        _ => panic!("The fee note must be notified"),
+    }
+
+    let events: Vec<DbEvent> = resumed.try_iter().collect();
+    assert_eq!(DbEvent::Note(1, stored[1]), events[0]);
+    assert_eq!(DbEvent::Note(2, stored[2]), events[1]);
+    assert_eq!(DbEvent::Note(3, note), events[2]);
+    assert_eq!(DbEvent::Nullifier(nullifier), events[3]);
+
+    let replayed: Vec<DbEvent> = db.subscribe_from(4).unwrap().try_iter().collect();
+    match replayed[0] {
+        DbEvent::Note(4, _) => (),
+        _ => panic!("The fee note must be replayed"),
+    }
+    assert_eq!(DbEvent::Nullifier(nullifier), replayed[1]);
+    assert_eq!(2, replayed.len());
+}
+
+#[test]
+fn failed_transaction_not_notified() {
+    let mut db = db::Db::<Blake2b>::default();
+
+    let sk = SecretKey::default();
+    let note: NoteVariant = TransparentNote::output(&sk.public_key(), 10).0.into();
+    let idx = db.store_unspent_note(note).unwrap();
+    let note = db.fetch_note(idx).unwrap();
+
+    let merkle_opening = db.opening(&note).unwrap();
+    let input = note.to_transaction_input(merkle_opening, sk).unwrap();
+
+    let mut tx = Transaction::default();
+    tx.push_input(input).unwrap();
+    db.store_transaction(&tx, 0).unwrap();
+
+    let live = db.subscribe();
+    let root = db.nullifier_root();
+
+    match db.store_transaction(&tx, 0) {
+        Err(Error::DoubleSpending) => (),
+        _ => panic!("The note is already spent"),
+    }
+
+    assert!(live.try_iter().next().is_none());
+    assert_eq!(root, db.nullifier_root());
+    assert!(db.fetch_note(2).is_err());
+}
+
 #[test]
 #[ignore]
 fn double_spending() {
@@ -73,8 +188,11 @@ fn double_spending() {
     let variant: NoteVariant = note.into();
     let base_note_idx = db.store_unspent_note(variant).unwrap();
     let merkle_opening = db.opening(&variant).unwrap();
-    tx.push_input(note.to_transaction_input(merkle_opening, sk_base).unwrap())
-        .unwrap();
+    tx.push_input(
+        note.to_transaction_input(merkle_opening, sk_base.clone())
+            .unwrap(),
+    )
+    .unwrap();
 
     let sk_receiver = SecretKey::default();
     let pk = sk_receiver.public_key();
@@ -99,7 +217,7 @@ fn double_spending() {
     tx.prove().unwrap();
     tx.verify().unwrap();
 
-    let inserted = db.store_bulk_transactions(&[tx]).unwrap();
+    let inserted = db.store_bulk_transactions(&[tx], 0).unwrap();
 
     let mut tx_ok = Transaction::default();
 
@@ -111,7 +229,7 @@ fn double_spending() {
         .collect();
     assert_eq!(1, note.len());
     let note = note[0];
-    assert_eq!(95, note.value(Some(&vk)));
+    assert_eq!(95, note.value(Some(&vk)).unwrap());
     let variant: NoteVariant = note.into();
     let merkle_opening = db.opening(&variant).unwrap();
     tx_ok
@@ -150,7 +268,7 @@ fn double_spending() {
 
     let vk = sk_base.view_key();
     let note = db.fetch_note(base_note_idx).unwrap();
-    assert_eq!(100, note.value(Some(&vk)));
+    assert_eq!(100, note.value(Some(&vk)).unwrap());
     let merkle_opening = db.opening(&note).unwrap();
     tx_double_spending
         .push_input(note.to_transaction_input(merkle_opening, sk_base).unwrap())
@@ -182,6 +300,35 @@ fn double_spending() {
     tx_double_spending.verify().unwrap();
 
     assert!(db
-        .store_bulk_transactions(&[tx_ok, tx_double_spending])
+        .store_bulk_transactions(&[tx_ok, tx_double_spending], 0)
         .is_err());
 }
+
+#[test]
+fn locked_note_spending() {
+    let mut db = db::Db::<Blake2b>::default();
+
+    let sk = SecretKey::default();
+    let pk = sk.public_key();
+
+    let mut note: NoteVariant = ObfuscatedNote::output(&pk, 10).0.into();
+    note.set_unlock_height(100);
+    let idx = db.store_unspent_note(note).unwrap();
+    let note = db.fetch_note(idx).unwrap();
+    assert_eq!(100, note.unlock_height());
+
+    let merkle_opening = db.opening(&note).unwrap();
+    let input = note.to_transaction_input(merkle_opening, sk).unwrap();
+    let nullifier = *input.nullifier();
+    let mut tx = Transaction::default();
+    tx.push_input(input).unwrap();
+
+    assert!(matches!(
+        db.store_transaction(&tx, 99),
+        Err(Error::NoteLocked)
+    ));
+    assert!(db.fetch_nullifier(&nullifier).unwrap().is_none());
+
+    db.store_transaction(&tx, 100).unwrap();
+    assert!(db.fetch_nullifier(&nullifier).unwrap().is_some());
+}
@@ -0,0 +1,473 @@
+use crate::note::obfuscated::{
+    ENCRYPTED_ASSET_SIZE, ENCRYPTED_BLINDING_FACTOR_SIZE, ENCRYPTED_VALUE_SIZE,
+};
+use crate::{
+    utils, BlsScalar, Error, JubJubAffine, JubJubExtended, JubJubScalar, Nonce, Note, NoteType,
+    NoteVariant, Nullifier, ObfuscatedNote, PublicKey, SecretKey, Transaction, TransactionInput,
+    TransactionItem, TransactionOutput, TransparentNote, ViewKey, ENCRYPTED_MEMO_SIZE, NONCEBYTES,
+};
+
+use std::fmt;
+
+use bincode::{deserialize, serialize};
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Decode a lowercase hex string of exactly `len` bytes
+fn decode(s: &str, len: usize) -> Result<Vec<u8>, Error> {
+    if s.len() != len * 2
+        || !s
+            .bytes()
+            .all(|c| c.is_ascii_digit() || (b'a'..=b'f').contains(&c))
+    {
+        return Err(Error::InvalidParameters);
+    }
+
+    hex::decode(s).map_err(|_| Error::InvalidParameters)
+}
+
+fn point_to_bytes(p: &JubJubExtended) -> [u8; utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE] {
+    JubJubAffine::from(p).to_bytes()
+}
+
+fn point_from_bytes(bytes: &[u8]) -> Result<JubJubExtended, Error> {
+    utils::deserialize_strict_jubjub(bytes)
+}
+
+fn scalar_from_bytes(bytes: &[u8]) -> Result<JubJubScalar, Error> {
+    utils::deserialize_jubjub_scalar(bytes)
+}
+
+fn deserialize_str<'de, D: Deserializer<'de>, T>(
+    deserializer: D,
+    parse: impl FnOnce(&str) -> Result<T, Error>,
+) -> Result<T, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    let result = parse(s.as_str()).map_err(de::Error::custom);
+
+    // The string may carry the hex of a secret
+    utils::wipe_bytes(s.into_bytes().as_mut_slice());
+
+    result
+}
+
+/// Serialize the hex of a secret, wiping the intermediate string
+fn serialize_secret<S: Serializer, T: fmt::LowerHex>(
+    secret: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let hex = format!("{:x}", secret);
+    let result = serializer.serialize_str(hex.as_str());
+    utils::wipe_bytes(hex.into_bytes().as_mut_slice());
+
+    result
+}
+
+/// Compressed point as 32 bytes hex
+struct Point(JubJubExtended);
+
+impl Serialize for Point {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(point_to_bytes(&self.0)))
+    }
+}
+
+impl<'de> Deserialize<'de> for Point {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_str(deserializer, |s| {
+            point_from_bytes(decode(s, utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE)?.as_slice())
+                .map(Point)
+        })
+    }
+}
+
+/// Little-endian JubJub scalar as 32 bytes hex
+struct Scalar(JubJubScalar);
+
+impl Serialize for Scalar {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(self.0.to_bytes()))
+    }
+}
+
+impl<'de> Deserialize<'de> for Scalar {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_str(deserializer, |s| {
+            scalar_from_bytes(decode(s, utils::JUBJUB_SCALAR_SERIALIZED_SIZE)?.as_slice())
+                .map(Scalar)
+        })
+    }
+}
+
+/// Little-endian BLS scalar as 32 bytes hex
+struct Bls(BlsScalar);
+
+impl Serialize for Bls {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(self.0.to_bytes()))
+    }
+}
+
+impl<'de> Deserialize<'de> for Bls {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_str(deserializer, |s| {
+            utils::deserialize_bls_scalar(decode(s, utils::BLS_SCALAR_SERIALIZED_SIZE)?.as_slice())
+                .map(Bls)
+        })
+    }
+}
+
+/// Raw bytes as hex, with the length validated by the owner
+struct Bytes(Vec<u8>);
+
+impl Serialize for Bytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for Bytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_str(deserializer, |s| decode(s, s.len() / 2).map(Bytes))
+    }
+}
+
+impl Bytes {
+    fn exact(self, len: usize) -> Result<Vec<u8>, Error> {
+        if self.0.len() != len {
+            return Err(Error::InvalidParameters);
+        }
+
+        Ok(self.0)
+    }
+}
+
+/// `a || b`, the same hex of the [`fmt::Display`](std::fmt::Display) implementation
+impl Serialize for SecretKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_secret(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_str(deserializer, |s| {
+            let mut bytes = decode(s, utils::JUBJUB_SCALAR_SERIALIZED_SIZE * 2)?;
+            let (a, b) = bytes.split_at(utils::JUBJUB_SCALAR_SERIALIZED_SIZE);
+            let sk = scalar_from_bytes(a)
+                .and_then(|a| scalar_from_bytes(b).map(|b| SecretKey::new(a, b)));
+
+            utils::wipe_bytes(bytes.as_mut_slice());
+
+            sk
+        })
+    }
+}
+
+/// `a || B`, the same hex of the [`fmt::Display`](std::fmt::Display) implementation
+impl Serialize for ViewKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_secret(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ViewKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_str(deserializer, |s| {
+            let mut bytes = decode(
+                s,
+                utils::JUBJUB_SCALAR_SERIALIZED_SIZE + utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE,
+            )?;
+            let (a, B) = bytes.split_at(utils::JUBJUB_SCALAR_SERIALIZED_SIZE);
+            let vk =
+                scalar_from_bytes(a).and_then(|a| point_from_bytes(B).map(|B| ViewKey::new(a, B)));
+
+            utils::wipe_bytes(bytes.as_mut_slice());
+
+            vk
+        })
+    }
+}
+
+/// `A || B`, the same hex of the [`fmt::Display`](std::fmt::Display) implementation
+impl Serialize for PublicKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:x}", self))
+    }
+}
+
+impl<'de> Deserialize<'de> for PublicKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_str(deserializer, |s| {
+            let bytes = decode(s, utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE * 2)?;
+            let (A, B) = bytes.split_at(utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE);
+
+            Ok(PublicKey::new(point_from_bytes(A)?, point_from_bytes(B)?))
+        })
+    }
+}
+
+impl Serialize for Nullifier {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Bls(*self.s()).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Nullifier {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Bls::deserialize(deserializer).map(|s| Nullifier::new(s.0))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransparentNoteRepr {
+    value_commitment: Point,
+    nonce: Bytes,
+    R: Point,
+    pk_r: Point,
+    idx: u64,
+    value: u64,
+    blinding_factor: Scalar,
+    encrypted_memo: Bytes,
+    asset: u64,
+    unlock_height: u64,
+}
+
+impl Serialize for TransparentNote {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        TransparentNoteRepr {
+            value_commitment: Point(*self.value_commitment()),
+            nonce: Bytes(self.nonce().0.to_vec()),
+            R: Point(*self.R()),
+            pk_r: Point(*self.pk_r()),
+            idx: self.idx(),
+            value: self.value,
+            blinding_factor: Scalar(self.blinding_factor),
+            encrypted_memo: Bytes(self.encrypted_memo.to_vec()),
+            asset: self.asset,
+            unlock_height: self.unlock_height(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TransparentNote {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let note = TransparentNoteRepr::deserialize(deserializer)?;
+        let nonce = note.nonce.exact(NONCEBYTES).map_err(de::Error::custom)?;
+        let encrypted_memo = note
+            .encrypted_memo
+            .exact(ENCRYPTED_MEMO_SIZE)
+            .map_err(de::Error::custom)?;
+
+        Ok(TransparentNote::new(
+            note.value_commitment.0,
+            Nonce::from_slice(nonce.as_slice())
+                .ok_or_else(|| de::Error::custom(Error::InvalidParameters))?,
+            note.R.0,
+            note.pk_r.0,
+            note.idx,
+            note.value,
+            note.blinding_factor.0,
+            utils::safe_48_chunk(encrypted_memo.as_slice()),
+            note.asset,
+            note.unlock_height,
+        ))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ObfuscatedNoteRepr {
+    value_commitment: Point,
+    nonce: Bytes,
+    R: Point,
+    pk_r: Point,
+    idx: u64,
+    encrypted_value: Bytes,
+    encrypted_blinding_factor: Bytes,
+    encrypted_memo: Bytes,
+    encrypted_asset: Bytes,
+    unlock_height: u64,
+}
+
+impl Serialize for ObfuscatedNote {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ObfuscatedNoteRepr {
+            value_commitment: Point(*self.value_commitment()),
+            nonce: Bytes(self.nonce().0.to_vec()),
+            R: Point(*self.R()),
+            pk_r: Point(*self.pk_r()),
+            idx: self.idx(),
+            encrypted_value: Bytes(self.encrypted_value.to_vec()),
+            encrypted_blinding_factor: Bytes(self.encrypted_blinding_factor.to_vec()),
+            encrypted_memo: Bytes(self.encrypted_memo.to_vec()),
+            encrypted_asset: Bytes(self.encrypted_asset.to_vec()),
+            unlock_height: self.unlock_height(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ObfuscatedNote {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let note = ObfuscatedNoteRepr::deserialize(deserializer)?;
+
+        let nonce = note.nonce.exact(NONCEBYTES).map_err(de::Error::custom)?;
+        let encrypted_value = note
+            .encrypted_value
+            .exact(ENCRYPTED_VALUE_SIZE)
+            .map_err(de::Error::custom)?;
+        let encrypted_blinding_factor = note
+            .encrypted_blinding_factor
+            .exact(ENCRYPTED_BLINDING_FACTOR_SIZE)
+            .map_err(de::Error::custom)?;
+        let encrypted_memo = note
+            .encrypted_memo
+            .exact(ENCRYPTED_MEMO_SIZE)
+            .map_err(de::Error::custom)?;
+        let encrypted_asset = note
+            .encrypted_asset
+            .exact(ENCRYPTED_ASSET_SIZE)
+            .map_err(de::Error::custom)?;
+
+        Ok(ObfuscatedNote::new(
+            note.value_commitment.0,
+            Nonce::from_slice(nonce.as_slice())
+                .ok_or_else(|| de::Error::custom(Error::InvalidParameters))?,
+            note.R.0,
+            note.pk_r.0,
+            note.idx,
+            utils::safe_24_chunk(encrypted_value.as_slice()),
+            utils::safe_48_chunk(encrypted_blinding_factor.as_slice()),
+            utils::safe_48_chunk(encrypted_memo.as_slice()),
+            utils::safe_24_chunk(encrypted_asset.as_slice()),
+            note.unlock_height,
+        ))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", deny_unknown_fields)]
+enum NoteVariantRepr {
+    Transparent(TransparentNote),
+    Obfuscated(ObfuscatedNote),
+}
+
+/// Externally tagged as `{ "transparent": { .. } }` or `{ "obfuscated": { .. } }`
+impl Serialize for NoteVariant {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            NoteVariant::Transparent(note) => NoteVariantRepr::Transparent(*note),
+            NoteVariant::Obfuscated(note) => NoteVariantRepr::Obfuscated(*note),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for NoteVariant {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match NoteVariantRepr::deserialize(deserializer)? {
+            NoteVariantRepr::Transparent(note) => NoteVariant::Transparent(note),
+            NoteVariantRepr::Obfuscated(note) => NoteVariant::Obfuscated(note),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransactionInputRepr {
+    nullifier: Nullifier,
+    merkle_root: Bls,
+    unlock_height: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransactionRepr {
+    inputs: Vec<TransactionInputRepr>,
+    outputs: Vec<NoteVariant>,
+    fee: NoteVariant,
+    proof: Option<Bytes>,
+}
+
+/// Only the public data of the transaction is serialized, as in its [`io::Read`](std::io::Read)
+/// implementation: the nullifiers, merkle roots and unlock heights of the inputs, the output
+/// notes, the fee note and the proof
+impl Serialize for Transaction {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let proof = self
+            .proof()
+            .map(|p| serialize(p).map(Bytes))
+            .transpose()
+            .map_err(ser::Error::custom)?;
+
+        TransactionRepr {
+            inputs: self
+                .inputs()
+                .iter()
+                .map(|i| TransactionInputRepr {
+                    nullifier: *i.nullifier(),
+                    merkle_root: Bls(i.merkle_root),
+                    unlock_height: i.unlock_height,
+                })
+                .collect(),
+            outputs: self.outputs().iter().map(|o| o.note().clone()).collect(),
+            fee: self.fee().note().clone(),
+            proof,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Transaction {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = TransactionRepr::deserialize(deserializer)?;
+
+        transaction_from_repr(repr).map_err(de::Error::custom)
+    }
+}
+
+fn transaction_from_repr(repr: TransactionRepr) -> Result<Transaction, Error> {
+    let mut tx = Transaction::default();
+
+    for input in repr.inputs {
+        tx.push_input(TransactionInput::obfuscated(
+            input.nullifier,
+            input.merkle_root.0,
+            input.unlock_height,
+        ))?;
+    }
+
+    for note in repr.outputs {
+        tx.push_output(TransactionOutput::new(
+            note,
+            0,
+            0,
+            JubJubScalar::default(),
+            PublicKey::default(),
+        ))?;
+    }
+
+    // The fee is public, and its value is required to rank the transaction
+    if repr.fee.note() != NoteType::Transparent {
+        return Err(Error::InvalidParameters);
+    }
+    let asset = repr.fee.asset(None)?;
+    let value = repr.fee.value(None)?;
+    let blinding_factor = repr.fee.blinding_factor(None)?;
+    tx.set_fee(TransactionOutput::new(
+        repr.fee,
+        asset,
+        value,
+        blinding_factor,
+        PublicKey::default(),
+    ));
+
+    if let Some(proof) = repr.proof {
+        let proof = deserialize(proof.0.as_slice()).map_err(|_| Error::InvalidProof)?;
+        tx.set_proof(proof);
+    }
+
+    Ok(tx)
+}
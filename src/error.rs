@@ -39,6 +39,46 @@ pub enum Error {
     NotFound,
     /// Attempt to double spend
     DoubleSpending,
+    /// The provided proof is not consistent with the expected root
+    InvalidProof,
+    /// The transaction is already present
+    DuplicatedTransaction,
+    /// The transaction exceeds the maximum size accepted by the mempool
+    TransactionTooLarge,
+    /// The mempool is full, and the fee is not high enough to evict pending transactions
+    MempoolFull,
+    /// The number of words, or bytes of entropy, of the mnemonic is not supported
+    InvalidMnemonicLength,
+    /// The word is not part of the mnemonic wordlist
+    InvalidMnemonicWord(String),
+    /// The checksum of the mnemonic phrase is not consistent with its entropy
+    InvalidMnemonicChecksum,
+    /// The address belongs to a different, or unknown, network
+    InvalidNetwork,
+    /// The checksum of the encoded data is not consistent
+    InvalidChecksum,
+    /// The password can't decrypt the keystore
+    WrongPassword,
+    /// The keystore is malformed, or its ciphertext was modified
+    CorruptedKeystore,
+    /// The unspent notes don't cover the requested value
+    InsufficientBalance,
+    /// The ciphertext can't be authenticated with the provided key
+    DecryptionFailed,
+    /// The note can't be spent before its unlock height
+    NoteLocked,
+    /// The bytes are not the canonical encoding of the decoded value
+    NonCanonicalEncoding,
+    /// The point is of small order
+    SmallOrderPoint,
+    /// The point is the identity
+    IdentityPoint,
+    /// The share, or partial secret, is not consistent with the commitments of the shared key
+    InvalidShare,
+    /// The key doesn't own the note, or doesn't match the shared key or the template owner
+    KeyMismatch,
+    /// The persisted data was written with an unsupported format version
+    UnsupportedVersion(u8),
 }
 
 impl Error {
@@ -74,7 +114,7 @@ impl Into<io::Error> for Error {
     fn into(self) -> io::Error {
         match self {
             Error::Io(e) => e,
-            _ => io::Error::new(io::ErrorKind::Other, format!("{}", self)),
+            _ => io::Error::new(io::ErrorKind::Other, self),
         }
     }
 }
@@ -92,5 +132,14 @@ impl From<Error> for tonic::Status {
     }
 }
 
-from_error!(io::Error, Io);
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        // Recover the errors converted with `Into<io::Error>`
+        match e.get_ref().and_then(|i| i.downcast_ref::<Error>()) {
+            Some(_) => *e.into_inner().unwrap().downcast::<Error>().unwrap(),
+            None => Error::Io(e),
+        }
+    }
+}
+
 from_error!(fmt::Error, Fmt);
@@ -0,0 +1,143 @@
+use crate::{utils, Error, PublicKey};
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use sha2::{Digest, Sha256};
+
+const PK_SIZE: usize = utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE * 2;
+const CHECKSUM_SIZE: usize = 4;
+const ADDRESS_SIZE: usize = 1 + PK_SIZE + CHECKSUM_SIZE;
+
+/// Network an [`Address`] is valid for, encoded as the version byte of the address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Network {
+    /// Main network
+    Mainnet,
+    /// Public test network
+    Testnet,
+    /// Local development network
+    Devnet,
+}
+
+impl Network {
+    /// Version byte prefixed to the encoded address
+    pub fn version(&self) -> u8 {
+        match self {
+            Network::Mainnet => 0x50,
+            Network::Testnet => 0x51,
+            Network::Devnet => 0x52,
+        }
+    }
+}
+
+impl TryFrom<u8> for Network {
+    type Error = Error;
+
+    fn try_from(version: u8) -> Result<Self, Self::Error> {
+        match version {
+            0x50 => Ok(Network::Mainnet),
+            0x51 => Ok(Network::Testnet),
+            0x52 => Ok(Network::Devnet),
+            _ => Err(Error::InvalidNetwork),
+        }
+    }
+}
+
+/// Human-readable representation of a [`PublicKey`] bound to a [`Network`]
+///
+/// The address is the base58 encoding of `version || A || B || checksum`, where `A` and `B` are
+/// the compressed points of the public key, and the checksum is the first 4 bytes of
+/// `SHA-256(SHA-256(version || A || B))`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Address {
+    network: Network,
+    pk: PublicKey,
+}
+
+impl Address {
+    /// Create a new address of the public key for the provided network
+    pub fn new(network: Network, pk: PublicKey) -> Self {
+        Address { network, pk }
+    }
+
+    /// Parse an address, rejecting it if it doesn't belong to the expected network
+    pub fn parse(s: &str, network: Network) -> Result<Self, Error> {
+        let address = Address::from_str(s)?;
+
+        if address.network != network {
+            return Err(Error::InvalidNetwork);
+        }
+
+        Ok(address)
+    }
+
+    /// Network of the address
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Public key the address represents
+    pub fn public_key(&self) -> &PublicKey {
+        &self.pk
+    }
+
+    fn checksum(payload: &[u8]) -> [u8; CHECKSUM_SIZE] {
+        let hash = Sha256::digest(&Sha256::digest(payload)[..]);
+
+        let mut checksum = [0x00u8; CHECKSUM_SIZE];
+        checksum.copy_from_slice(&hash[..CHECKSUM_SIZE]);
+
+        checksum
+    }
+}
+
+impl From<Address> for PublicKey {
+    fn from(address: Address) -> Self {
+        address.pk
+    }
+}
+
+impl FromStr for Address {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = bs58::decode(s)
+            .into_vec()
+            .map_err(|_| Error::InvalidParameters)?;
+
+        if bytes.len() != ADDRESS_SIZE {
+            return Err(Error::InvalidParameters);
+        }
+
+        let (payload, checksum) = bytes.split_at(ADDRESS_SIZE - CHECKSUM_SIZE);
+        if Address::checksum(payload) != checksum {
+            return Err(Error::InvalidChecksum);
+        }
+
+        let network = Network::try_from(payload[0])?;
+
+        let A = utils::deserialize_compressed_jubjub(&payload[1..1 + PK_SIZE / 2])
+            .map_err(|_| Error::InvalidPoint)?;
+        let B = utils::deserialize_compressed_jubjub(&payload[1 + PK_SIZE / 2..])
+            .map_err(|_| Error::InvalidPoint)?;
+
+        Ok(Address::new(network, PublicKey::new(A, B)))
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pk: [u8; PK_SIZE] = (&self.pk).into();
+
+        let mut bytes = [0x00u8; ADDRESS_SIZE];
+        bytes[0] = self.network.version();
+        bytes[1..1 + PK_SIZE].copy_from_slice(&pk[..]);
+
+        let checksum = Address::checksum(&bytes[..ADDRESS_SIZE - CHECKSUM_SIZE]);
+        bytes[ADDRESS_SIZE - CHECKSUM_SIZE..].copy_from_slice(&checksum[..]);
+
+        write!(f, "{}", bs58::encode(&bytes[..]).into_string())
+    }
+}
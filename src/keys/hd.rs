@@ -0,0 +1,117 @@
+use crate::{utils, Error, JubJubScalar, SecretKey};
+
+use std::fmt;
+
+use blake2::{Blake2b, Digest};
+use subtle::{Choice, ConstantTimeEq};
+
+/// Minimum length of the seed of a master key
+pub const MIN_SEED_SIZE: usize = 16;
+/// Maximum length of the seed of a master key
+pub const MAX_SEED_SIZE: usize = 64;
+
+const MASTER_LABEL: &[u8] = b"phoenix-hd-master";
+const CHILD_LABEL: &[u8] = b"phoenix-hd-child";
+const SECRET_A_LABEL: &[u8] = b"phoenix-hd-a";
+const SECRET_B_LABEL: &[u8] = b"phoenix-hd-b";
+
+/// Node of the derivation tree, composed of a secret and a chain code
+///
+/// Every step is a Blake2b-512 hash of a length-prefixed domain label followed by its inputs.
+/// The algorithm is independent from any random number generator implementation, so the
+/// derived keys are stable across versions and implementations.
+///
+/// * Master: `I = H("phoenix-hd-master", seed)`, where the secret is `I[0..32]` and the chain
+///   code is `I[32..64]`
+/// * Child `i`: `I = H("phoenix-hd-child", chain_code, secret, i as u32 little-endian)`, split as
+///   above. Every child is hardened; the public key of the parent can't derive it.
+/// * [`SecretKey`]: `a = H("phoenix-hd-a", secret)` and `b = H("phoenix-hd-b", secret)`, both
+///   interpreted as little-endian 512-bit integers reduced modulo the JubJub scalar field order.
+#[derive(Clone)]
+pub struct ExtendedSecretKey {
+    secret: [u8; 32],
+    chain_code: [u8; 32],
+}
+
+impl ConstantTimeEq for ExtendedSecretKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.secret[..].ct_eq(&other.secret[..]) & self.chain_code[..].ct_eq(&other.chain_code[..])
+    }
+}
+
+impl PartialEq for ExtendedSecretKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(&other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for ExtendedSecretKey {}
+
+impl Drop for ExtendedSecretKey {
+    fn drop(&mut self) {
+        utils::wipe_bytes(&mut self.secret);
+        utils::wipe_bytes(&mut self.chain_code);
+    }
+}
+
+impl fmt::Debug for ExtendedSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ExtendedSecretKey {{ .. }}")
+    }
+}
+
+impl ExtendedSecretKey {
+    /// Create the root of the derivation tree from a seed of 16 to 64 bytes
+    pub fn master(seed: &[u8]) -> Result<Self, Error> {
+        if seed.len() < MIN_SEED_SIZE || seed.len() > MAX_SEED_SIZE {
+            return Err(Error::InvalidParameters);
+        }
+
+        Ok(Self::from_hash(hash(MASTER_LABEL, &[seed])))
+    }
+
+    /// Derive the child on the provided index
+    pub fn child(&self, index: u32) -> Self {
+        Self::from_hash(hash(
+            CHILD_LABEL,
+            &[&self.chain_code, &self.secret, &index.to_le_bytes()],
+        ))
+    }
+
+    /// Derive the descendant on the provided path of indexes, starting from this node
+    pub fn derive_path(&self, path: &[u32]) -> Self {
+        path.iter()
+            .fold(self.clone(), |key, index| key.child(*index))
+    }
+
+    /// Create the [`SecretKey`] of this node
+    pub fn secret_key(&self) -> SecretKey {
+        let a = JubJubScalar::from_bytes_wide(&hash(SECRET_A_LABEL, &[&self.secret]));
+        let b = JubJubScalar::from_bytes_wide(&hash(SECRET_B_LABEL, &[&self.secret]));
+
+        SecretKey::new(a, b)
+    }
+
+    fn from_hash(bytes: [u8; 64]) -> Self {
+        let mut secret = [0x00u8; 32];
+        let mut chain_code = [0x00u8; 32];
+
+        secret.copy_from_slice(&bytes[0..32]);
+        chain_code.copy_from_slice(&bytes[32..64]);
+
+        ExtendedSecretKey { secret, chain_code }
+    }
+}
+
+pub(crate) fn hash(label: &[u8], inputs: &[&[u8]]) -> [u8; 64] {
+    let mut hasher = Blake2b::new();
+
+    hasher.update(&[label.len() as u8]);
+    hasher.update(label);
+    inputs.iter().for_each(|i| hasher.update(i));
+
+    let mut bytes = [0x00u8; 64];
+    bytes.copy_from_slice(&hasher.finalize()[..]);
+
+    bytes
+}
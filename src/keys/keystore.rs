@@ -0,0 +1,270 @@
+use crate::{utils, Error, SecretKey};
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sodiumoxide::crypto::secretbox;
+
+/// Version of the keystore format produced by this implementation
+pub const KEYSTORE_VERSION: u32 = 1;
+/// Maximum memory usage of the key derivation, in KiB
+pub const MAX_KDF_MEMORY: u32 = 4 * 1024 * 1024;
+/// Maximum number of passes of the key derivation
+pub const MAX_KDF_ITERATIONS: u32 = 64;
+/// Maximum number of lanes of the key derivation
+pub const MAX_KDF_PARALLELISM: u32 = 64;
+
+const KDF_ALGORITHM: &str = "argon2id";
+const CIPHER_ALGORITHM: &str = "xsalsa20poly1305";
+const SALT_SIZE: usize = 16;
+const SK_SIZE: usize = utils::JUBJUB_SCALAR_SERIALIZED_SIZE * 2;
+
+/// Cost parameters of the Argon2id key derivation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    /// Memory usage, in KiB
+    pub memory: u32,
+    /// Number of passes over the memory
+    pub iterations: u32,
+    /// Number of lanes
+    pub parallelism: u32,
+}
+
+impl KdfParams {
+    /// Return `true` if the parameters don't exceed the maximum cost accepted by the keystore, so
+    /// a crafted keystore can't exhaust the resources of the host
+    pub fn is_bounded(&self) -> bool {
+        self.memory <= MAX_KDF_MEMORY
+            && self.iterations <= MAX_KDF_ITERATIONS
+            && self.parallelism <= MAX_KDF_PARALLELISM
+    }
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams {
+            memory: 64 * 1024,
+            iterations: 3,
+            parallelism: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct KdfSection {
+    algorithm: String,
+    memory: u32,
+    iterations: u32,
+    parallelism: u32,
+    salt: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CipherSection {
+    algorithm: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Password-protected [`SecretKey`], serialized as versioned JSON
+///
+/// The encryption key is derived from the password with Argon2id, and the serialized secret key
+/// is sealed with XSalsa20-Poly1305. The SHA-256 of the ciphertext is stored along with it, so a
+/// corrupted file is reported as [`Error::CorruptedKeystore`], while a failed authentication of
+/// an intact ciphertext is reported as [`Error::WrongPassword`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    version: u32,
+    kdf: KdfSection,
+    cipher: CipherSection,
+    checksum: String,
+}
+
+impl Keystore {
+    /// Encrypt the secret key under the password with the default [`KdfParams`]
+    pub fn encrypt(sk: &SecretKey, password: &[u8]) -> Result<Self, Error> {
+        Self::encrypt_with_params(sk, password, KdfParams::default())
+    }
+
+    /// Encrypt the secret key under the password with the provided [`KdfParams`]
+    ///
+    /// The parameters exceeding the maximum cost are rejected with [`Error::InvalidParameters`]
+    pub fn encrypt_with_params(
+        sk: &SecretKey,
+        password: &[u8],
+        params: KdfParams,
+    ) -> Result<Self, Error> {
+        if !params.is_bounded() {
+            return Err(Error::InvalidParameters);
+        }
+
+        let mut salt = [0x00u8; SALT_SIZE];
+        rand::thread_rng().fill_bytes(&mut salt);
+
+        let key = derive_key(password, &salt, &params)?;
+        let nonce = secretbox::gen_nonce();
+
+        let mut plaintext: [u8; SK_SIZE] = sk.into();
+        let ciphertext = secretbox::seal(&plaintext, &nonce, &key);
+        utils::wipe_bytes(&mut plaintext);
+
+        Ok(Keystore {
+            version: KEYSTORE_VERSION,
+            kdf: KdfSection {
+                algorithm: KDF_ALGORITHM.to_string(),
+                memory: params.memory,
+                iterations: params.iterations,
+                parallelism: params.parallelism,
+                salt: hex::encode(&salt[..]),
+            },
+            cipher: CipherSection {
+                algorithm: CIPHER_ALGORITHM.to_string(),
+                nonce: hex::encode(&nonce[..]),
+                ciphertext: hex::encode(ciphertext.as_slice()),
+            },
+            checksum: hex::encode(&Sha256::digest(ciphertext.as_slice())[..]),
+        })
+    }
+
+    /// Decrypt the secret key with the password
+    ///
+    /// A keystore with KDF parameters exceeding the maximum cost, or rejected by Argon2, is
+    /// reported as [`Error::CorruptedKeystore`]
+    pub fn decrypt(&self, password: &[u8]) -> Result<SecretKey, Error> {
+        if self.version != KEYSTORE_VERSION
+            || self.kdf.algorithm != KDF_ALGORITHM
+            || self.cipher.algorithm != CIPHER_ALGORITHM
+        {
+            return Err(Error::CorruptedKeystore);
+        }
+
+        let salt = decode_hex(self.kdf.salt.as_str())?;
+        let nonce = decode_hex(self.cipher.nonce.as_str())?;
+        let nonce =
+            secretbox::Nonce::from_slice(nonce.as_slice()).ok_or(Error::CorruptedKeystore)?;
+        let ciphertext = decode_hex(self.cipher.ciphertext.as_str())?;
+        let checksum = decode_hex(self.checksum.as_str())?;
+
+        if Sha256::digest(ciphertext.as_slice()).as_slice() != checksum.as_slice() {
+            return Err(Error::CorruptedKeystore);
+        }
+
+        let params = self.kdf_params();
+        if !params.is_bounded() {
+            return Err(Error::CorruptedKeystore);
+        }
+
+        let key =
+            derive_key(password, salt.as_slice(), &params).map_err(|_| Error::CorruptedKeystore)?;
+
+        let mut plaintext = secretbox::open(ciphertext.as_slice(), &nonce, &key)
+            .map_err(|_| Error::WrongPassword)?;
+        if plaintext.len() != SK_SIZE {
+            return Err(Error::CorruptedKeystore);
+        }
+
+        let a = utils::deserialize_jubjub_scalar(&plaintext[..SK_SIZE / 2]);
+        let b = utils::deserialize_jubjub_scalar(&plaintext[SK_SIZE / 2..]);
+        utils::wipe_bytes(&mut plaintext);
+
+        Ok(SecretKey::new(
+            a.map_err(|_| Error::CorruptedKeystore)?,
+            b.map_err(|_| Error::CorruptedKeystore)?,
+        ))
+    }
+
+    /// Version of the format of the keystore
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Parameters used to derive the encryption key from the password
+    pub fn kdf_params(&self) -> KdfParams {
+        KdfParams {
+            memory: self.kdf.memory,
+            iterations: self.kdf.iterations,
+            parallelism: self.kdf.parallelism,
+        }
+    }
+
+    /// Serialize the keystore to JSON
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self).map_err(Error::generic)
+    }
+
+    /// Deserialize a keystore from JSON
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str(json).map_err(|_| Error::CorruptedKeystore)
+    }
+
+    /// Write the keystore to the provided path
+    ///
+    /// On unix, the file is readable and writable only by its owner
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut file = options.open(path)?;
+
+        // The mode is applied only to new files
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        }
+
+        file.write_all(self.to_json()?.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Read a keystore from the provided path
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_json(fs::read_to_string(path)?.as_str())
+    }
+}
+
+/// Encrypt the secret key under the password, and write the keystore to the provided path
+pub fn save<P: AsRef<Path>>(sk: &SecretKey, password: &[u8], path: P) -> Result<(), Error> {
+    Keystore::encrypt(sk, password)?.save(path)
+}
+
+/// Read the keystore from the provided path, and decrypt the secret key with the password
+pub fn load<P: AsRef<Path>>(path: P, password: &[u8]) -> Result<SecretKey, Error> {
+    Keystore::load(path)?.decrypt(password)
+}
+
+fn derive_key(password: &[u8], salt: &[u8], params: &KdfParams) -> Result<secretbox::Key, Error> {
+    let config = argon2::Config {
+        variant: argon2::Variant::Argon2id,
+        version: argon2::Version::Version13,
+        mem_cost: params.memory,
+        time_cost: params.iterations,
+        lanes: params.parallelism,
+        thread_mode: argon2::ThreadMode::Sequential,
+        secret: &[],
+        ad: &[],
+        hash_length: secretbox::KEYBYTES as u32,
+    };
+
+    let mut bytes =
+        argon2::hash_raw(password, salt, &config).map_err(|_| Error::InvalidParameters)?;
+    let key = secretbox::Key::from_slice(bytes.as_slice());
+    utils::wipe_bytes(bytes.as_mut_slice());
+
+    key.ok_or(Error::Generic)
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>, Error> {
+    hex::decode(s).map_err(|_| Error::CorruptedKeystore)
+}
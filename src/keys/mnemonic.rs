@@ -0,0 +1,204 @@
+use crate::{utils, Error, ExtendedSecretKey, SecretKey};
+
+use std::fmt;
+use std::str::FromStr;
+
+use hmac::Hmac;
+use rand::RngCore;
+use sha2::{Digest, Sha256, Sha512};
+use subtle::{Choice, ConstantTimeEq};
+use unicode_normalization::UnicodeNormalization;
+
+/// Number of PBKDF2 iterations to derive the seed from the phrase
+pub const SEED_ITERATIONS: usize = 2048;
+/// Size of the seed derived from the phrase
+pub const SEED_SIZE: usize = 64;
+
+const BITS_PER_WORD: usize = 11;
+
+lazy_static::lazy_static! {
+    static ref WORDLIST: Vec<&'static str> = include_str!("wordlist/english.txt")
+        .split_whitespace()
+        .collect();
+}
+
+/// Backup phrase of a secret, compatible with the english BIP39 encoding
+///
+/// The phrase encodes 128 to 256 bits of entropy, followed by the first `entropy bits / 32` bits
+/// of its SHA-256 as checksum, in groups of 11 bits mapped to the wordlist.
+///
+/// The seed is defined as `PBKDF2-HMAC-SHA512(phrase, "mnemonic" + passphrase, 2048)`, and is
+/// the input of the [`ExtendedSecretKey`] derivation.
+#[derive(Clone)]
+pub struct Mnemonic {
+    entropy: Vec<u8>,
+    words: Vec<&'static str>,
+}
+
+impl ConstantTimeEq for Mnemonic {
+    /// The words are defined by the entropy, so only the entropy is compared
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.entropy.as_slice().ct_eq(other.entropy.as_slice())
+    }
+}
+
+impl PartialEq for Mnemonic {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(&other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for Mnemonic {}
+
+impl Drop for Mnemonic {
+    fn drop(&mut self) {
+        utils::wipe_bytes(self.entropy.as_mut_slice());
+    }
+}
+
+impl fmt::Debug for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Mnemonic {{ words: {} }}", self.words.len())
+    }
+}
+
+impl Mnemonic {
+    /// Create a mnemonic from 16, 20, 24, 28 or 32 bytes of entropy
+    pub fn from_entropy(entropy: &[u8]) -> Result<Self, Error> {
+        if entropy.len() < 16 || entropy.len() > 32 || entropy.len() % 4 != 0 {
+            return Err(Error::InvalidMnemonicLength);
+        }
+
+        let checksum = Sha256::digest(entropy);
+        let checksum_bits = entropy.len() * 8 / 32;
+
+        let bits: Vec<bool> = bytes_to_bits(entropy)
+            .chain(bytes_to_bits(&checksum[..]).take(checksum_bits))
+            .collect();
+
+        let words = bits
+            .chunks(BITS_PER_WORD)
+            .map(|c| WORDLIST[c.iter().fold(0, |idx, bit| (idx << 1) | *bit as usize)])
+            .collect();
+
+        Ok(Mnemonic {
+            entropy: entropy.to_vec(),
+            words,
+        })
+    }
+
+    /// Generate a random mnemonic with the provided number of words: 12, 15, 18, 21 or 24
+    pub fn generate<R: RngCore>(rng: &mut R, words: usize) -> Result<Self, Error> {
+        if words % 3 != 0 {
+            return Err(Error::InvalidMnemonicLength);
+        }
+
+        let mut entropy = vec![0x00u8; words / 3 * 4];
+        rng.fill_bytes(entropy.as_mut_slice());
+
+        Self::from_entropy(entropy.as_slice())
+    }
+
+    /// Entropy encoded by the phrase
+    pub fn entropy(&self) -> &[u8] {
+        self.entropy.as_slice()
+    }
+
+    /// Words of the phrase
+    pub fn words(&self) -> &[&'static str] {
+        self.words.as_slice()
+    }
+
+    /// Phrase with the words separated by a single space
+    pub fn phrase(&self) -> String {
+        self.words.join(" ")
+    }
+
+    /// Derive the seed of the phrase, protected by an optional passphrase
+    ///
+    /// The passphrase is normalized to NFKD, as specified by BIP-39, so its composed and
+    /// decomposed forms derive the same seed
+    pub fn to_seed(&self, passphrase: &str) -> [u8; SEED_SIZE] {
+        let mut salt: Vec<u8> = format!("mnemonic{}", passphrase)
+            .nfkd()
+            .collect::<String>()
+            .into_bytes();
+        let mut seed = [0x00u8; SEED_SIZE];
+
+        pbkdf2::pbkdf2::<Hmac<Sha512>>(
+            self.phrase().as_bytes(),
+            salt.as_slice(),
+            SEED_ITERATIONS,
+            &mut seed,
+        );
+        utils::wipe_bytes(salt.as_mut_slice());
+
+        seed
+    }
+
+    /// Derive the [`SecretKey`] on the provided path of the seed of the phrase
+    pub fn secret_key(&self, passphrase: &str, path: &[u32]) -> Result<SecretKey, Error> {
+        SecretKey::derive(&self.to_seed(passphrase), path)
+    }
+
+    /// Derive the root [`ExtendedSecretKey`] of the seed of the phrase
+    pub fn extended_secret_key(&self, passphrase: &str) -> Result<ExtendedSecretKey, Error> {
+        ExtendedSecretKey::master(&self.to_seed(passphrase))
+    }
+}
+
+impl FromStr for Mnemonic {
+    type Err = Error;
+
+    fn from_str(phrase: &str) -> Result<Self, Self::Err> {
+        let indexes = phrase
+            .split_whitespace()
+            .map(|w| {
+                let w = w.to_lowercase();
+                WORDLIST
+                    .binary_search(&w.as_str())
+                    .map_err(|_| Error::InvalidMnemonicWord(w.clone()))
+            })
+            .collect::<Result<Vec<usize>, Error>>()?;
+
+        if indexes.len() < 12 || indexes.len() > 24 || indexes.len() % 3 != 0 {
+            return Err(Error::InvalidMnemonicLength);
+        }
+
+        let bits: Vec<bool> = indexes
+            .iter()
+            .flat_map(|idx| (0..BITS_PER_WORD).rev().map(move |i| (idx >> i) & 1 == 1))
+            .collect();
+
+        let checksum_bits = bits.len() / 33;
+        let entropy_bits = bits.len() - checksum_bits;
+
+        let entropy: Vec<u8> = bits[..entropy_bits]
+            .chunks(8)
+            .map(|c| c.iter().fold(0u8, |byte, bit| (byte << 1) | *bit as u8))
+            .collect();
+
+        let mnemonic = Mnemonic::from_entropy(entropy.as_slice())?;
+        if indexes
+            .iter()
+            .zip(mnemonic.words.iter())
+            .any(|(idx, w)| WORDLIST[*idx] != *w)
+        {
+            return Err(Error::InvalidMnemonicChecksum);
+        }
+
+        Ok(mnemonic)
+    }
+}
+
+impl fmt::Display for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.phrase())
+    }
+}
+
+fn bytes_to_bits(bytes: &[u8]) -> impl Iterator<Item = bool> + '_ {
+    bytes
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1 == 1))
+}
@@ -1,9 +1,29 @@
+pub use address::{Address, Network};
+pub use hd::ExtendedSecretKey;
+#[cfg(feature = "serde-support")]
+pub use keystore::{KdfParams, Keystore};
+pub use mnemonic::Mnemonic;
 pub use public::PublicKey;
 pub use secret::SecretKey;
+pub use subaddress::SubaddressTable;
+pub use threshold::{PartialSecret, SecretShare, ShareCommitments};
 pub use view::ViewKey;
 
+/// Checksummed address encoding of the public keys
+pub mod address;
+/// Hierarchical deterministic key derivation
+pub mod hd;
+/// Password-encrypted storage of the secret keys, as JSON. Enabled by the `serde-support` feature
+#[cfg(feature = "serde-support")]
+pub mod keystore;
+/// Mnemonic phrase backup of the keys
+pub mod mnemonic;
 mod public;
 mod secret;
+/// Subaddresses sharing a single view key
+pub mod subaddress;
+/// Threshold sharing of the spend secret
+pub mod threshold;
 mod view;
 
 #[cfg(test)]
@@ -1,4 +1,4 @@
-use crate::{rpc, utils, Error, JubJubAffine, JubJubExtended, SecretKey};
+use crate::{crypto, rpc, utils, Address, Error, JubJubAffine, JubJubExtended, Network, SecretKey};
 
 use std::convert::{TryFrom, TryInto};
 use std::fmt;
@@ -27,6 +27,18 @@ impl PartialEq for PublicKey {
 
 impl Eq for PublicKey {}
 
+impl PublicKey {
+    /// Return the checksummed [`Address`] of the public key for the provided network
+    pub fn to_address(&self, network: Network) -> Address {
+        Address::new(network, *self)
+    }
+
+    /// Verify a signature created with [`SecretKey::sign`] against the point `B`
+    pub fn verify(&self, msg: &[u8], signature: &crypto::Signature) -> bool {
+        signature.verify(&self.B, msg)
+    }
+}
+
 impl Default for PublicKey {
     fn default() -> Self {
         SecretKey::default().public_key()
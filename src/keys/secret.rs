@@ -1,4 +1,6 @@
-use crate::{rpc, utils, Error, JubJubExtended, JubJubScalar, PublicKey, ViewKey};
+use crate::{
+    crypto, rpc, utils, Error, ExtendedSecretKey, JubJubExtended, JubJubScalar, PublicKey, ViewKey,
+};
 
 use jubjub::GENERATOR;
 use std::convert::{TryFrom, TryInto};
@@ -6,17 +8,47 @@ use std::fmt;
 use std::ops::Mul;
 
 use rand::RngCore;
+use subtle::{Choice, ConstantTimeEq};
 use unprolix::{Constructor, Getters, Setters};
 
 /// Secret pair of a and b
 ///
 /// It is used to create a note nullifier via secret b
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Constructor, Getters, Setters)]
+///
+/// The scalars are wiped from memory when the key is dropped
+#[derive(Clone, Constructor, Getters, Setters)]
 pub struct SecretKey {
     a: JubJubScalar,
     b: JubJubScalar,
 }
 
+impl ConstantTimeEq for SecretKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.a.ct_eq(&other.a) & self.b.ct_eq(&other.b)
+    }
+}
+
+impl PartialEq for SecretKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(&other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for SecretKey {}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        utils::wipe(&mut self.a);
+        utils::wipe(&mut self.b);
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretKey {{ .. }}")
+    }
+}
+
 impl Default for SecretKey {
     fn default() -> Self {
         SecretKey {
@@ -35,6 +67,18 @@ impl SecretKey {
         SecretKey::new(a, b)
     }
 
+    /// Derive a [`SecretKey`] from a seed and a path of indexes. See [`ExtendedSecretKey`]
+    pub fn derive(seed: &[u8], path: &[u32]) -> Result<Self, Error> {
+        Ok(ExtendedSecretKey::master(seed)?
+            .derive_path(path)
+            .secret_key())
+    }
+
+    /// Sign a message with the secret `b`, verifiable with [`PublicKey::verify`]
+    pub fn sign(&self, msg: &[u8]) -> crypto::Signature {
+        crypto::Signature::sign(&self.b, msg)
+    }
+
     /// Derive the secret to deterministically construct a [`PublicKey`]
     pub fn public_key(&self) -> PublicKey {
         let A = JubJubExtended::from(GENERATOR).mul(&self.a);
@@ -0,0 +1,108 @@
+use crate::keys::hd;
+use crate::{JubJubAffine, JubJubExtended, JubJubScalar, Note, PublicKey, SecretKey, ViewKey};
+
+use std::collections::BTreeMap;
+use std::ops::Mul;
+
+use jubjub::GENERATOR;
+
+const SUBADDRESS_LABEL: &[u8] = b"phoenix-subaddress";
+
+/// Scalar `m_i = H("phoenix-subaddress", a, i)` used to offset the spend key of the subaddress
+/// `i`. The index `0` is the main address, so its offset is zero.
+fn offset(a: &JubJubScalar, index: u32) -> JubJubScalar {
+    if index == 0 {
+        return JubJubScalar::zero();
+    }
+
+    JubJubScalar::from_bytes_wide(&hd::hash(
+        SUBADDRESS_LABEL,
+        &[&a.to_bytes()[..], &index.to_le_bytes()],
+    ))
+}
+
+impl SecretKey {
+    /// Secret key of the subaddress on the provided index, with `b_i = b + m_i`
+    ///
+    /// See [`SubaddressTable`]
+    pub fn subaddress(&self, index: u32) -> SecretKey {
+        SecretKey::new(*self.a(), self.b() + offset(self.a(), index))
+    }
+}
+
+impl ViewKey {
+    /// Public key of the subaddress on the provided index, with `B_i = B + m_i · G`
+    ///
+    /// See [`SubaddressTable`]
+    pub fn subaddress(&self, index: u32) -> PublicKey {
+        let m = JubJubExtended::from(GENERATOR).mul(&offset(self.a(), index));
+
+        PublicKey::new(*self.public_key().A(), self.B() + m)
+    }
+
+    /// View key of the subaddress on the provided index
+    pub fn subaddress_view_key(&self, index: u32) -> ViewKey {
+        ViewKey::new(*self.a(), *self.subaddress(index).B())
+    }
+}
+
+/// Lookup table of the subaddresses of a [`ViewKey`]
+///
+/// Every subaddress `i` shares the secret `a` of the view key, and offsets the spend point as
+/// `B_i = B + m_i · G`, where `m_i` is derived from `a` and `i`. The index `0` is the main
+/// address. A note sent to `B_i` has `PKr = H(a · R) · G + B_i`, so its owner recovers `B_i` with
+/// the view key only, and spends it with [`SecretKey::subaddress`].
+///
+/// The subaddresses share the point `A`, so they are linkable by anyone that collects them. They
+/// only avoid exposing the same public key to distinct senders.
+#[derive(Debug, Clone)]
+pub struct SubaddressTable {
+    vk: ViewKey,
+    keys: BTreeMap<[u8; 32], u32>,
+}
+
+impl SubaddressTable {
+    /// Create a table of the subaddresses `0..count` of the view key
+    pub fn new(vk: ViewKey, count: u32) -> Self {
+        let mut table = SubaddressTable {
+            vk,
+            keys: BTreeMap::new(),
+        };
+
+        (0..count).for_each(|i| table.insert(i));
+
+        table
+    }
+
+    /// Include the subaddress on the provided index to the table
+    pub fn insert(&mut self, index: u32) {
+        let B = *self.vk.subaddress(index).B();
+
+        self.keys.insert(JubJubAffine::from(B).to_bytes(), index);
+    }
+
+    /// View key of the table
+    pub fn view_key(&self) -> &ViewKey {
+        &self.vk
+    }
+
+    /// Number of subaddresses of the table
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Return true if the table has no subaddresses
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Return the index of the subaddress with the provided spend point, if present
+    pub fn lookup(&self, B: &JubJubExtended) -> Option<u32> {
+        self.keys.get(&JubJubAffine::from(B).to_bytes()).copied()
+    }
+
+    /// Return the index of the subaddress the note was sent to, if owned by the view key
+    pub fn index_of<N: Note + ?Sized>(&self, note: &N) -> Option<u32> {
+        note.subaddress_index(self)
+    }
+}
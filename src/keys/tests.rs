@@ -1,6 +1,22 @@
-use crate::{PublicKey, SecretKey, ViewKey};
+#[cfg(feature = "serde-support")]
+use crate::keys::keystore::{self, KdfParams, Keystore};
+use crate::keys::threshold::{self, PartialSecret, SecretShare, ShareCommitments};
+use crate::{
+    crypto, Address, Error, ExtendedSecretKey, JubJubAffine, Mnemonic, Network, Note,
+    NoteGenerator, ObfuscatedNote, PublicKey, SecretKey, Transaction, TransactionItem,
+    TransparentNote, ViewKey,
+};
 
 use std::convert::TryFrom;
+use std::fmt;
+use std::ptr;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+#[cfg(feature = "serde-support")]
+use tempdir::TempDir;
 
 #[test]
 fn sk_from_bytes() {
@@ -23,3 +39,616 @@ fn keys_encoding() {
     assert_eq!(vk, ViewKey::try_from(format!("{}", vk)).unwrap());
     assert_eq!(pk, PublicKey::try_from(format!("{}", pk)).unwrap());
 }
+
+#[test]
+fn hd_derivation_vectors() {
+    let seed: Vec<u8> = (0u8..32).collect();
+    let master = ExtendedSecretKey::master(seed.as_slice()).unwrap();
+
+    assert_eq!(
+        "4f3bc51bae1be845dbb426ef1b1a583695044185d40846c8f50a306343085809\
+         3455637ac3e16d495321437a8adc4bf22f9ada59589e98eee5d665509fd8d803",
+        format!("{:x}", master.secret_key())
+    );
+    assert_eq!(
+        "e91830a784c7ee892ca16b6ab2d3a6b5c1b235b21d31609b3bd6e2f74ec8ac0d\
+         ee444e03233917eb8fad79cedb34fe1af8242b82af0c7af6f59ed6563131aa04",
+        format!("{:x}", master.child(0).secret_key())
+    );
+    assert_eq!(
+        "87d8f1424e582e2990e1e53fe151db3463db4bb89f3d2977b372efa83a08170a\
+         df28c68f8aa3d23ddf9ebc32d2da5283098d9974b8459f6bb1a65e3b70dc3f04",
+        format!("{:x}", master.derive_path(&[0, 1]).secret_key())
+    );
+    assert_eq!(
+        "b3313cd02a2dfe86390c60714f1aace40da44763a00e42061f29e88605fa6204\
+         a8554579a7364a9b2f9835b15d632f963dbbbcddd50b3b7167b3a9a1b2dd240d",
+        format!("{:x}", master.child(0x8000_0007).secret_key())
+    );
+
+    let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+    assert_eq!(
+        "b9510b22f7156b6af591bd6f5e151b78087c5b878ce466f2ffa270d55234020b\
+         3ee4da2020364829c60debe60146a98b55d1f79357e978ab8139dc44659fdc00",
+        format!(
+            "{:x}",
+            SecretKey::derive(seed.as_slice(), &[44, 0]).unwrap()
+        )
+    );
+}
+
+#[test]
+fn hd_derivation_seed_size() {
+    assert!(ExtendedSecretKey::master(&[0u8; 15]).is_err());
+    assert!(ExtendedSecretKey::master(&[0u8; 65]).is_err());
+
+    let a = SecretKey::derive(&[7u8; 64], &[1, 2, 3]).unwrap();
+    let b = SecretKey::derive(&[7u8; 64], &[1, 2, 3]).unwrap();
+    let c = SecretKey::derive(&[7u8; 64], &[1, 2, 4]).unwrap();
+
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+}
+
+#[test]
+fn mnemonic_vectors() {
+    let mnemonic = Mnemonic::from_entropy(&[0x00u8; 16]).unwrap();
+    assert_eq!(
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+         abandon about",
+        mnemonic.phrase()
+    );
+    assert_eq!(
+        "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e5349553\
+         1f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+        hex::encode(&mnemonic.to_seed("TREZOR")[..])
+    );
+    assert_eq!(
+        "ad09749df34161bc7833fe2cd7be80e950bc205464523b64d93ea0b5befeaa00\
+         53bfa1a313b2140bee2a25ede4245107bbe1f5cd331e3929ec4996abf05c830d",
+        format!(
+            "{:x}",
+            mnemonic.extended_secret_key("TREZOR").unwrap().secret_key()
+        )
+    );
+    assert_eq!(
+        "47bc6eb4856c5d01e04593ee770eb7a002b4fe59ff03c29bf786ecc44ce0d70d\
+         c18df5f2f9fc7857e3cf73082b70a431d12860a8d53b386f6c50e42393dd6001",
+        format!("{:x}", mnemonic.secret_key("TREZOR", &[44, 0]).unwrap())
+    );
+
+    let mnemonic = Mnemonic::from_entropy(&[0x7fu8; 16]).unwrap();
+    assert_eq!(
+        "legal winner thank year wave sausage worth useful legal winner thank yellow",
+        mnemonic.phrase()
+    );
+    assert_eq!(
+        "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6f\
+         a457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607",
+        hex::encode(&mnemonic.to_seed("TREZOR")[..])
+    );
+    assert_eq!(
+        "feb11aee5d6c7ed49c05a18d643430db46031385af4e0076c2902ad0a06dc406\
+         fdd157216be74d5c0b34ec3ef1c9c9cdbb3004c51e39647bdf705917fb966409",
+        format!(
+            "{:x}",
+            mnemonic.extended_secret_key("TREZOR").unwrap().secret_key()
+        )
+    );
+
+    let entropy: Vec<u8> = (0u8..32).collect();
+    let mnemonic = Mnemonic::from_entropy(entropy.as_slice()).unwrap();
+    assert_eq!(
+        "abandon amount liar amount expire adjust cage candy arch gather drum bullet absurd \
+         math era live bid rhythm alien crouch range attend journey unaware",
+        mnemonic.phrase()
+    );
+    assert_eq!(
+        "e00fc08b7fa18c78eb9a943c2ce3903eb52d2590be5aadfe83b39465ac66c3a6\
+         490a134828a61d311d62974b4ce0708d9a714c529f3e45b296640a9297c6f383",
+        hex::encode(&mnemonic.to_seed("TREZOR")[..])
+    );
+
+    // The composed and decomposed forms of the passphrase are equivalent
+    assert_eq!(
+        &mnemonic.to_seed("caf\u{e9}")[..],
+        &mnemonic.to_seed("cafe\u{301}")[..]
+    );
+    assert_ne!(
+        &mnemonic.to_seed("caf\u{e9}")[..],
+        &mnemonic.to_seed("cafe")[..]
+    );
+}
+
+#[test]
+fn mnemonic_restore() {
+    let mut rng = rand::thread_rng();
+
+    for words in [12, 15, 18, 21, 24].iter() {
+        let mnemonic = Mnemonic::generate(&mut rng, *words).unwrap();
+        let restored = Mnemonic::from_str(mnemonic.to_string().as_str()).unwrap();
+
+        assert_eq!(*words, mnemonic.words().len());
+        assert_eq!(mnemonic, restored);
+        assert_eq!(
+            mnemonic.secret_key("", &[0]).unwrap(),
+            restored.secret_key("", &[0]).unwrap()
+        );
+    }
+
+    let phrase = "Legal Winner thank year wave sausage worth useful legal winner thank yellow";
+    assert_eq!(
+        Mnemonic::from_entropy(&[0x7fu8; 16]).unwrap(),
+        Mnemonic::from_str(phrase).unwrap()
+    );
+}
+
+#[test]
+fn mnemonic_invalid() {
+    let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+                  abandon abandon";
+    match Mnemonic::from_str(phrase) {
+        Err(Error::InvalidMnemonicChecksum) => (),
+        _ => panic!("The checksum of the phrase is invalid"),
+    }
+
+    let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+                  abandon phoenix";
+    match Mnemonic::from_str(phrase) {
+        Err(Error::InvalidMnemonicWord(w)) => assert_eq!("phoenix", w.as_str()),
+        _ => panic!("The word is not part of the wordlist"),
+    }
+
+    match Mnemonic::from_str("abandon abandon about") {
+        Err(Error::InvalidMnemonicLength) => (),
+        _ => panic!("The phrase is too short"),
+    }
+
+    assert!(Mnemonic::from_entropy(&[0x00u8; 15]).is_err());
+    assert!(Mnemonic::from_entropy(&[0x00u8; 36]).is_err());
+    assert!(Mnemonic::generate(&mut rand::thread_rng(), 13).is_err());
+}
+
+#[test]
+fn address_encoding() {
+    let pk = SecretKey::default().public_key();
+
+    for network in [Network::Mainnet, Network::Testnet, Network::Devnet].iter() {
+        let address = pk.to_address(*network);
+        let parsed = Address::parse(address.to_string().as_str(), *network).unwrap();
+
+        assert_eq!(address, parsed);
+        assert_eq!(pk, *parsed.public_key());
+    }
+
+    let address = pk.to_address(Network::Testnet).to_string();
+    match Address::parse(address.as_str(), Network::Mainnet) {
+        Err(Error::InvalidNetwork) => (),
+        _ => panic!("The address belongs to the test network"),
+    }
+}
+
+#[test]
+fn address_invalid() {
+    let pk = SecretKey::default().public_key();
+    let address = pk.to_address(Network::Mainnet).to_string();
+
+    // Replace a single character of the address
+    let mut typo = address.clone().into_bytes();
+    typo[10] = if typo[10] == b'2' { b'3' } else { b'2' };
+    let typo = String::from_utf8(typo).unwrap();
+    match Address::from_str(typo.as_str()) {
+        Err(Error::InvalidChecksum) => (),
+        _ => panic!("The checksum is not consistent with the address"),
+    }
+
+    assert!(Address::from_str(&address[1..]).is_err());
+    assert!(Address::from_str("0OIl").is_err());
+
+    // The point with `y = 2` is not on the curve
+    let mut bytes = vec![Network::Mainnet.version()];
+    bytes.extend_from_slice(&[0x00u8; 64]);
+    bytes[1] = 0x02;
+    let checksum = Sha256::digest(&Sha256::digest(bytes.as_slice())[..]);
+    bytes.extend_from_slice(&checksum[..4]);
+
+    let off_curve = bs58::encode(bytes.as_slice()).into_string();
+    match Address::from_str(off_curve.as_str()) {
+        Err(Error::InvalidPoint) => (),
+        _ => panic!("The point is not on the curve"),
+    }
+}
+
+#[cfg(feature = "serde-support")]
+const TEST_KDF_PARAMS: KdfParams = KdfParams {
+    memory: 256,
+    iterations: 1,
+    parallelism: 1,
+};
+
+#[test]
+#[cfg(feature = "serde-support")]
+fn keystore_save_load() {
+    let dir = TempDir::new("keystore").unwrap();
+    let path = dir.path().join("sk.json");
+
+    let sk = SecretKey::default();
+    Keystore::encrypt_with_params(&sk, b"password", TEST_KDF_PARAMS)
+        .unwrap()
+        .save(&path)
+        .unwrap();
+
+    assert_eq!(sk, keystore::load(&path, b"password").unwrap());
+
+    let keystore = Keystore::load(&path).unwrap();
+    assert_eq!(1, keystore.version());
+    assert_eq!(TEST_KDF_PARAMS, keystore.kdf_params());
+
+    match keystore.decrypt(b"wrong password") {
+        Err(Error::WrongPassword) => (),
+        _ => panic!("The password is not the one used to encrypt the keystore"),
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(0o600, mode & 0o777);
+    }
+}
+
+#[test]
+#[cfg(feature = "serde-support")]
+fn keystore_kdf_limits() {
+    let sk = SecretKey::default();
+
+    let mut params = TEST_KDF_PARAMS;
+    params.memory = keystore::MAX_KDF_MEMORY + 1;
+    match Keystore::encrypt_with_params(&sk, b"password", params) {
+        Err(Error::InvalidParameters) => (),
+        _ => panic!("The memory cost exceeds the limit"),
+    }
+
+    let keystore = Keystore::encrypt_with_params(&sk, b"password", TEST_KDF_PARAMS).unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(keystore.to_json().unwrap().as_str()).unwrap();
+
+    // Above the limits, or rejected by Argon2
+    for (field, value) in [
+        ("memory", u64::from(u32::max_value())),
+        ("iterations", u64::from(keystore::MAX_KDF_ITERATIONS + 1)),
+        ("parallelism", u64::from(keystore::MAX_KDF_PARALLELISM + 1)),
+        ("iterations", 0),
+        ("parallelism", 0),
+    ]
+    .iter()
+    {
+        let mut json = json.clone();
+        json["kdf"][*field] = (*value).into();
+
+        match Keystore::from_json(json.to_string().as_str()).and_then(|k| k.decrypt(b"password")) {
+            Err(Error::CorruptedKeystore) => (),
+            _ => panic!("The KDF parameters of the keystore are not acceptable"),
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "serde-support")]
+fn keystore_corrupted() {
+    let sk = SecretKey::default();
+    let keystore = Keystore::encrypt_with_params(&sk, b"password", TEST_KDF_PARAMS).unwrap();
+
+    let mut json: serde_json::Value =
+        serde_json::from_str(keystore.to_json().unwrap().as_str()).unwrap();
+    let mut ciphertext = hex::decode(json["cipher"]["ciphertext"].as_str().unwrap()).unwrap();
+    ciphertext[0] ^= 0x01;
+    json["cipher"]["ciphertext"] = hex::encode(ciphertext).into();
+
+    match Keystore::from_json(json.to_string().as_str()).and_then(|k| k.decrypt(b"password")) {
+        Err(Error::CorruptedKeystore) => (),
+        _ => panic!("The ciphertext of the keystore was modified"),
+    }
+
+    match Keystore::from_json("{ \"version\": 1 }") {
+        Err(Error::CorruptedKeystore) => (),
+        _ => panic!("The keystore is malformed"),
+    }
+}
+
+#[test]
+fn secrets_debug_redacted() {
+    let sk = SecretKey::default();
+    let vk = sk.view_key();
+
+    let a = hex::encode(sk.a().to_bytes());
+    let b = hex::encode(sk.b().to_bytes());
+
+    for debug in [format!("{:?}", sk), format!("{:?}", vk)].iter() {
+        assert!(!debug.contains(a.as_str()));
+        assert!(!debug.contains(b.as_str()));
+    }
+}
+
+/// Assert the equality of every variant against the reference is consistent with its
+/// constant-time equality
+fn assert_ct_eq_consistent<T: ConstantTimeEq + PartialEq + fmt::Debug>(
+    reference: &T,
+    variants: &[(T, bool)],
+) {
+    for (variant, equal) in variants.iter() {
+        assert_eq!(*equal, reference.ct_eq(variant).unwrap_u8() == 1);
+        assert_eq!(*equal, reference == variant);
+    }
+}
+
+/// Median duration of a batch of equality checks
+fn eq_duration<T: PartialEq>(a: &T, b: &T) -> Duration {
+    let mut samples: Vec<Duration> = (0..101)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..1000 {
+                let eq = a == b;
+                unsafe { ptr::read_volatile(&eq) };
+            }
+            start.elapsed()
+        })
+        .collect();
+
+    samples.sort();
+    samples[samples.len() / 2]
+}
+
+#[test]
+fn keys_ct_eq() {
+    let sk = SecretKey::default();
+    let other = SecretKey::default();
+
+    assert_ct_eq_consistent(
+        &sk,
+        &[
+            (sk.clone(), true),
+            (SecretKey::new(*other.a(), *sk.b()), false),
+            (SecretKey::new(*sk.a(), *other.b()), false),
+            (other.clone(), false),
+        ],
+    );
+
+    let vk = sk.view_key();
+    let other = other.view_key();
+    assert_ct_eq_consistent(
+        &vk,
+        &[
+            (vk.clone(), true),
+            (ViewKey::new(*other.a(), *vk.B()), false),
+            (ViewKey::new(*vk.a(), *other.B()), false),
+            (other.clone(), false),
+        ],
+    );
+
+    let seed = [0x01u8; 32];
+    let xsk = ExtendedSecretKey::master(&seed).unwrap();
+    assert_ct_eq_consistent(
+        &xsk,
+        &[
+            (xsk.clone(), true),
+            (xsk.child(0), false),
+            (ExtendedSecretKey::master(&[0x02u8; 32]).unwrap(), false),
+        ],
+    );
+
+    let mnemonic = Mnemonic::from_entropy(&[0x00u8; 16]).unwrap();
+    assert_ct_eq_consistent(
+        &mnemonic,
+        &[
+            (mnemonic.clone(), true),
+            (Mnemonic::from_entropy(&[0x01u8; 16]).unwrap(), false),
+            (Mnemonic::from_entropy(&[0x00u8; 32]).unwrap(), false),
+        ],
+    );
+}
+
+#[test]
+#[ignore = "timing measurement, flaky on shared runners; run with --ignored on an idle machine"]
+fn keys_ct_eq_timing() {
+    // The bound is lenient; it only catches a comparison that returns before reaching the
+    // remaining fields
+    let tolerance = |a: Duration, b: Duration| {
+        let (min, max) = if a < b { (a, b) } else { (b, a) };
+        max.as_nanos() < min.as_nanos() * 3
+    };
+
+    let sk = SecretKey::default();
+    let other = SecretKey::default();
+    let equal = eq_duration(&sk, &sk.clone());
+    let first = eq_duration(&sk, &SecretKey::new(*other.a(), *sk.b()));
+    let last = eq_duration(&sk, &SecretKey::new(*sk.a(), *other.b()));
+    assert!(tolerance(equal, first));
+    assert!(tolerance(equal, last));
+
+    let vk = sk.view_key();
+    let other = other.view_key();
+    let equal = eq_duration(&vk, &vk.clone());
+    let first = eq_duration(&vk, &ViewKey::new(*other.a(), *vk.B()));
+    assert!(tolerance(equal, first));
+}
+
+#[test]
+fn threshold_shares() {
+    let mut rng = rand::thread_rng();
+    let sk = SecretKey::default();
+    let vk = sk.view_key();
+
+    let (shares, commitments) = threshold::split(&sk, 2, 3, &mut rng).unwrap();
+    assert_eq!(3, shares.len());
+    assert_eq!(2, commitments.threshold());
+    assert_eq!(sk.public_key(), commitments.public_key());
+    assert!(shares.iter().all(|s| commitments.verify(s)));
+
+    let note = ObfuscatedNote::output(&sk.public_key(), 100).0;
+    let partials: Vec<PartialSecret> = shares
+        .iter()
+        .map(|s| s.partial_sk_r(&vk, &note).unwrap())
+        .collect();
+    assert!(partials
+        .iter()
+        .all(|p| commitments.verify_partial(&note, p)));
+
+    for (i, j) in [(0, 1), (0, 2), (2, 1)].iter() {
+        let pair = [partials[*i].clone(), partials[*j].clone()];
+        let merkle_opening = crypto::MerkleProof::mock(note.hash());
+        let input = threshold::combine(note, &vk, &pair, &commitments, merkle_opening).unwrap();
+
+        // The one-time key spends the note, but is not the shared key
+        assert_eq!(&note.generate_nullifier(&sk), input.nullifier());
+        assert_eq!(note.sk_r(&sk), note.sk_r(input.sk()));
+        assert_ne!(sk.b(), input.sk().b());
+        assert_eq!(100, input.value());
+    }
+
+    let combine = |partials: &[PartialSecret]| {
+        let merkle_opening = crypto::MerkleProof::mock(note.hash());
+        threshold::combine(note, &vk, partials, &commitments, merkle_opening)
+    };
+
+    assert!(matches!(
+        combine(&partials[..1]),
+        Err(Error::InvalidParameters)
+    ));
+
+    let duplicated = [partials[0].clone(), partials[0].clone()];
+    assert!(matches!(
+        combine(&duplicated),
+        Err(Error::InvalidParameters)
+    ));
+
+    let mut bytes = partials[1].to_bytes();
+    bytes[10] ^= 0x01;
+    if let Ok(tampered) = PartialSecret::from_bytes(&bytes[..]) {
+        assert!(!commitments.verify_partial(&note, &tampered));
+        assert!(matches!(
+            combine(&[partials[0].clone(), tampered]),
+            Err(Error::InvalidShare)
+        ));
+    }
+
+    let other = SecretKey::default();
+    let merkle_opening = crypto::MerkleProof::mock(note.hash());
+    assert!(matches!(
+        threshold::combine(
+            note,
+            &other.view_key(),
+            &partials,
+            &commitments,
+            merkle_opening
+        ),
+        Err(Error::KeyMismatch)
+    ));
+    assert!(matches!(
+        shares[0].partial_sk_r(&other.view_key(), &note),
+        Err(Error::KeyMismatch)
+    ));
+
+    assert!(threshold::split(&sk, 0, 3, &mut rng).is_err());
+    assert!(threshold::split(&sk, 4, 3, &mut rng).is_err());
+}
+
+#[test]
+fn threshold_spend() {
+    let sk = SecretKey::default();
+    let vk = sk.view_key();
+    let (shares, commitments) = threshold::split(&sk, 2, 3, &mut rand::thread_rng()).unwrap();
+
+    let note = ObfuscatedNote::output(&sk.public_key(), 100).0;
+
+    // The partial secrets are exchanged as bytes
+    let partials: Vec<PartialSecret> = shares[1..]
+        .iter()
+        .map(|s| s.partial_sk_r(&vk, &note).unwrap().to_bytes())
+        .map(|b| PartialSecret::from_bytes(&b[..]).unwrap())
+        .collect();
+
+    let merkle_opening = crypto::MerkleProof::mock(note.hash());
+    let input = threshold::combine(note, &vk, &partials, &commitments, merkle_opening).unwrap();
+
+    let mut tx = Transaction::default();
+    tx.push_input(input).unwrap();
+
+    let pk = SecretKey::default().public_key();
+    let (note, blinding_factor) = ObfuscatedNote::output(&pk, 95);
+    tx.push_output(note.to_transaction_output(95, blinding_factor, pk))
+        .unwrap();
+    let (note, blinding_factor) = TransparentNote::output(&pk, 5);
+    tx.set_fee(note.to_transaction_output(5, blinding_factor, pk));
+
+    tx.prove().unwrap();
+    tx.verify().unwrap();
+}
+
+#[test]
+fn threshold_serialization() {
+    let sk = SecretKey::default();
+    let (shares, commitments) = threshold::split(&sk, 3, 5, &mut rand::thread_rng()).unwrap();
+
+    let restored: Vec<SecretShare> = shares
+        .iter()
+        .map(|s| SecretShare::from_bytes(&s.to_bytes()[..]).unwrap())
+        .collect();
+    assert_eq!(shares, restored);
+
+    let restored_commitments = ShareCommitments::from_bytes(&commitments.to_bytes()).unwrap();
+    assert_eq!(commitments, restored_commitments);
+    assert!(restored.iter().all(|s| restored_commitments.verify(s)));
+
+    // The identity can't be a commitment
+    let mut bytes = commitments.to_bytes();
+    let len = bytes.len();
+    bytes[len - 32..].copy_from_slice(&JubJubAffine::identity().to_bytes()[..]);
+    assert!(ShareCommitments::from_bytes(&bytes).is_err());
+
+    assert!(!format!("{:?}", shares[0]).contains(&hex::encode(sk.b().to_bytes())));
+    assert!(!format!("{:?}", shares[0]).contains(&hex::encode(sk.a().to_bytes())));
+}
+
+#[test]
+#[cfg(feature = "serde-support")]
+fn keys_serde() {
+    let sk = SecretKey::default();
+    let vk = sk.view_key();
+    let pk = sk.public_key();
+
+    let json = serde_json::to_string(&sk).unwrap();
+    assert_eq!(format!("\"{}\"", sk), json);
+    assert_eq!(
+        sk,
+        serde_json::from_str::<SecretKey>(json.as_str()).unwrap()
+    );
+
+    let json = serde_json::to_string(&vk).unwrap();
+    assert_eq!(format!("\"{}\"", vk), json);
+    assert_eq!(vk, serde_json::from_str::<ViewKey>(json.as_str()).unwrap());
+
+    let json = serde_json::to_string(&pk).unwrap();
+    assert_eq!(format!("\"{}\"", pk), json);
+    assert_eq!(
+        pk,
+        serde_json::from_str::<PublicKey>(json.as_str()).unwrap()
+    );
+
+    // Only the lowercase hex of the exact size is accepted
+    let upper = format!("\"{:X}\"", pk);
+    assert!(serde_json::from_str::<PublicKey>(upper.as_str()).is_err());
+    let short = format!("\"{}\"", &pk.to_string()[2..]);
+    assert!(serde_json::from_str::<PublicKey>(short.as_str()).is_err());
+
+    // The scalars must be reduced
+    let unreduced = format!("\"{}{}\"", "ff".repeat(32), &sk.to_string()[64..]);
+    assert!(serde_json::from_str::<SecretKey>(unreduced.as_str()).is_err());
+
+    // The points must be on the curve
+    let mut off_curve = [0x00u8; 32];
+    off_curve[0] = 2;
+    let off_curve = format!("\"{}{}\"", hex::encode(off_curve), &pk.to_string()[64..]);
+    assert!(serde_json::from_str::<PublicKey>(off_curve.as_str()).is_err());
+}
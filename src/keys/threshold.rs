@@ -0,0 +1,391 @@
+use crate::{
+    crypto, note, utils, Error, JubJubAffine, JubJubExtended, JubJubScalar, Note, NoteGenerator,
+    PublicKey, SecretKey, TransactionInput, ViewKey,
+};
+
+use std::fmt;
+use std::ops::Mul;
+
+use jubjub::GENERATOR;
+use rand::RngCore;
+use subtle::{Choice, ConstantTimeEq};
+
+const SCALAR_SIZE: usize = utils::JUBJUB_SCALAR_SERIALIZED_SIZE;
+const POINT_SIZE: usize = utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE;
+
+/// Serialized size of a [`SecretShare`]
+pub const SECRET_SHARE_SIZE: usize = 4 + SCALAR_SIZE;
+
+/// Serialized size of a [`PartialSecret`]
+pub const PARTIAL_SECRET_SIZE: usize = 4 + SCALAR_SIZE;
+
+/// Share `b_i` of the spend secret `b` of a [`SecretKey`], split with [`split`]
+///
+/// The view secret `a` is not shared; every party holds the [`ViewKey`] of the shared
+/// [`PublicKey`] to scan its notes.
+///
+/// To spend a note, each party sends the [`PartialSecret`] `H(a · R) + b_i` of the note, created
+/// with [`SecretShare::partial_sk_r`], and any `threshold` of them are combined by [`combine`]
+/// into the one-time secret `sk_r = H(a · R) + b` of the note.
+///
+/// The combiner learns the spend secret `b`. `sk_r` is a witness of the transaction proof, and
+/// [`combine`] needs the view key to decrypt the note, so the combiner can compute
+/// `b = sk_r - H(a · R)` and spend every note of the key alone. The shares protect `b` at rest;
+/// the combiner must be trusted as much as the unsplit [`SecretKey`].
+#[derive(Clone)]
+pub struct SecretShare {
+    index: u32,
+    b: JubJubScalar,
+}
+
+impl ConstantTimeEq for SecretShare {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.index.ct_eq(&other.index) & self.b.ct_eq(&other.b)
+    }
+}
+
+impl PartialEq for SecretShare {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(&other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for SecretShare {}
+
+impl Drop for SecretShare {
+    fn drop(&mut self) {
+        utils::wipe(&mut self.b);
+    }
+}
+
+impl fmt::Debug for SecretShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretShare {{ index: {}, .. }}", self.index)
+    }
+}
+
+impl SecretShare {
+    /// Position of the share on the polynomial, starting from 1
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Evaluate the share of `sk_r = H(a · R) + b` for the provided note
+    ///
+    /// Return [`Error::KeyMismatch`] if the note is not owned by the view key
+    pub fn partial_sk_r<N: Note>(&self, vk: &ViewKey, note: &N) -> Result<PartialSecret, Error> {
+        let aR = note.R().mul(vk.a());
+
+        let aR = note::shared_secret_hashes(&aR)
+            .iter()
+            .find(|h| note.pk_r() == &(JubJubExtended::from(GENERATOR).mul(*h) + vk.B()))
+            .copied()
+            .ok_or(Error::KeyMismatch)?;
+
+        Ok(PartialSecret {
+            index: self.index,
+            s: aR + self.b,
+        })
+    }
+
+    /// Serialize the share as `index || b`
+    pub fn to_bytes(&self) -> [u8; SECRET_SHARE_SIZE] {
+        let mut bytes = [0x00u8; SECRET_SHARE_SIZE];
+
+        bytes[0..4].copy_from_slice(&self.index.to_le_bytes());
+        bytes[4..].copy_from_slice(&self.b.to_bytes()[..]);
+
+        bytes
+    }
+
+    /// Deserialize a share created with [`SecretShare::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let (index, b) = index_and_scalar(bytes, SECRET_SHARE_SIZE)?;
+
+        Ok(SecretShare { index, b })
+    }
+}
+
+/// Share `H(a · R) + b_i` of the one-time secret of a note, sent by a party to the combiner
+///
+/// It is created with [`SecretShare::partial_sk_r`], and checked with
+/// [`ShareCommitments::verify_partial`] without any secret.
+#[derive(Clone)]
+pub struct PartialSecret {
+    index: u32,
+    s: JubJubScalar,
+}
+
+impl ConstantTimeEq for PartialSecret {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.index.ct_eq(&other.index) & self.s.ct_eq(&other.s)
+    }
+}
+
+impl PartialEq for PartialSecret {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(&other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for PartialSecret {}
+
+impl Drop for PartialSecret {
+    fn drop(&mut self) {
+        utils::wipe(&mut self.s);
+    }
+}
+
+impl fmt::Debug for PartialSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PartialSecret {{ index: {}, .. }}", self.index)
+    }
+}
+
+impl PartialSecret {
+    /// Index of the share that created the partial secret
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Serialize the partial secret as `index || s`
+    pub fn to_bytes(&self) -> [u8; PARTIAL_SECRET_SIZE] {
+        let mut bytes = [0x00u8; PARTIAL_SECRET_SIZE];
+
+        bytes[0..4].copy_from_slice(&self.index.to_le_bytes());
+        bytes[4..].copy_from_slice(&self.s.to_bytes()[..]);
+
+        bytes
+    }
+
+    /// Deserialize a partial secret created with [`PartialSecret::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let (index, s) = index_and_scalar(bytes, PARTIAL_SECRET_SIZE)?;
+
+        Ok(PartialSecret { index, s })
+    }
+}
+
+fn index_and_scalar(bytes: &[u8], size: usize) -> Result<(u32, JubJubScalar), Error> {
+    if bytes.len() != size {
+        return Err(Error::InvalidParameters);
+    }
+
+    let mut index = [0x00u8; 4];
+    index.copy_from_slice(&bytes[0..4]);
+    let index = u32::from_le_bytes(index);
+    if index == 0 {
+        return Err(Error::InvalidParameters);
+    }
+
+    let scalar = utils::deserialize_jubjub_scalar(&bytes[4..])?;
+
+    Ok((index, scalar))
+}
+
+/// Commitments `c_j · G` to the coefficients of the sharing polynomial of `b`
+///
+/// The first commitment is the point `B` of the shared [`PublicKey`]. They are published by the
+/// dealer so every party can check its share with [`ShareCommitments::verify`], and the combiner
+/// can check the partial secrets with [`ShareCommitments::verify_partial`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareCommitments {
+    A: JubJubExtended,
+    coefficients: Vec<JubJubExtended>,
+}
+
+impl ShareCommitments {
+    /// Minimum number of shares required to spend a note
+    pub fn threshold(&self) -> usize {
+        self.coefficients.len()
+    }
+
+    /// Public key of the shared secret
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::new(self.A, self.coefficients[0])
+    }
+
+    /// Commitment `b_i · G` to the share of the provided index
+    fn share_point(&self, index: u32) -> JubJubExtended {
+        let x = JubJubScalar::from(index as u64);
+
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(JubJubExtended::identity(), |acc, c| acc.mul(&x) + c)
+    }
+
+    /// Return true if the share is a point of the committed polynomial
+    pub fn verify(&self, share: &SecretShare) -> bool {
+        JubJubExtended::from(GENERATOR).mul(&share.b) == self.share_point(share.index)
+    }
+
+    /// Return true if the partial secret was created for the note by a committed share
+    ///
+    /// `H(a · R) · G` is `PKr - B`, so `s_i · G` is checked against `PKr - B + b_i · G`
+    pub fn verify_partial<N: Note>(&self, note: &N, partial: &PartialSecret) -> bool {
+        let expected = note.pk_r() - self.coefficients[0] + self.share_point(partial.index);
+
+        JubJubExtended::from(GENERATOR).mul(&partial.s) == expected
+    }
+
+    /// Serialize the commitments as `A || count as u32 || c_0 || ... || c_t-1`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(POINT_SIZE + 4 + POINT_SIZE * self.threshold());
+
+        bytes.extend_from_slice(&JubJubAffine::from(self.A).to_bytes()[..]);
+        bytes.extend_from_slice(&(self.threshold() as u32).to_le_bytes());
+        self.coefficients
+            .iter()
+            .for_each(|c| bytes.extend_from_slice(&JubJubAffine::from(c).to_bytes()[..]));
+
+        bytes
+    }
+
+    /// Deserialize commitments created with [`ShareCommitments::to_bytes`]
+    ///
+    /// Every point must be canonically encoded, and neither the identity nor of small order
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < POINT_SIZE + 4 {
+            return Err(Error::InvalidParameters);
+        }
+
+        let A = utils::deserialize_strict_jubjub(&bytes[0..POINT_SIZE])?;
+
+        let mut count = [0x00u8; 4];
+        count.copy_from_slice(&bytes[POINT_SIZE..POINT_SIZE + 4]);
+        let count = u32::from_le_bytes(count) as usize;
+
+        let bytes = &bytes[POINT_SIZE + 4..];
+        if count == 0 || bytes.len() != count * POINT_SIZE {
+            return Err(Error::InvalidParameters);
+        }
+
+        let coefficients = bytes
+            .chunks(POINT_SIZE)
+            .map(utils::deserialize_strict_jubjub)
+            .collect::<Result<_, _>>()?;
+
+        Ok(ShareCommitments { A, coefficients })
+    }
+}
+
+/// Split the spend secret `b` of the key in `parties` shares, so any `threshold` of them can
+/// spend the notes of the key with [`combine`]
+pub fn split<R: RngCore>(
+    sk: &SecretKey,
+    threshold: usize,
+    parties: usize,
+    rng: &mut R,
+) -> Result<(Vec<SecretShare>, ShareCommitments), Error> {
+    if threshold == 0 || threshold > parties || parties > u32::max_value() as usize {
+        return Err(Error::InvalidParameters);
+    }
+
+    let mut coefficients = vec![*sk.b()];
+    (1..threshold).for_each(|_| coefficients.push(utils::gen_random_scalar_from_rng(rng)));
+
+    let shares = (1..=parties as u32)
+        .map(|index| {
+            let x = JubJubScalar::from(index as u64);
+            let b = coefficients
+                .iter()
+                .rev()
+                .fold(JubJubScalar::zero(), |acc, c| acc * x + c);
+
+            SecretShare { index, b }
+        })
+        .collect();
+
+    let commitments = ShareCommitments {
+        A: *sk.public_key().A(),
+        coefficients: coefficients
+            .iter()
+            .map(|c| JubJubExtended::from(GENERATOR).mul(c))
+            .collect(),
+    };
+
+    coefficients.iter_mut().for_each(utils::wipe);
+
+    Ok((shares, commitments))
+}
+
+/// Combine at least `threshold` partial secrets of a note into a [`TransactionInput`]
+///
+/// The one-time secret `sk_r` of the note is interpolated, and the input is created with a
+/// one-time [`SecretKey`] that derives `sk_r` for this note. `b` is not computed here, but the
+/// caller holds both `sk_r` and the view key, and so learns `b`; see [`SecretShare`].
+///
+/// Every partial secret is checked against the commitments, and an invalid one is reported with
+/// [`Error::InvalidShare`]. The view key decrypts the value and blinding factor of the note, and
+/// must belong to the shared key, or [`Error::KeyMismatch`] is returned.
+pub fn combine<N: NoteGenerator>(
+    note: N,
+    vk: &ViewKey,
+    partials: &[PartialSecret],
+    commitments: &ShareCommitments,
+    merkle_opening: crypto::MerkleProof,
+) -> Result<TransactionInput, Error> {
+    if vk.public_key() != commitments.public_key() {
+        return Err(Error::KeyMismatch);
+    }
+
+    let threshold = commitments.threshold();
+    if partials.len() < threshold {
+        return Err(Error::InvalidParameters);
+    }
+
+    let partials = &partials[..threshold];
+    for (i, partial) in partials.iter().enumerate() {
+        if partials[..i].iter().any(|p| p.index == partial.index) {
+            return Err(Error::InvalidParameters);
+        }
+
+        if !commitments.verify_partial(&note, partial) {
+            return Err(Error::InvalidShare);
+        }
+    }
+
+    // Lagrange interpolation of the partial secrets on zero
+    let mut sk_r = partials.iter().fold(JubJubScalar::zero(), |sk_r, partial| {
+        let x_i = JubJubScalar::from(partial.index as u64);
+
+        let (num, den) = partials.iter().filter(|p| p.index != partial.index).fold(
+            (JubJubScalar::one(), JubJubScalar::one()),
+            |(num, den), p| {
+                let x_j = JubJubScalar::from(p.index as u64);
+                (num * x_j, den * (x_j - x_i))
+            },
+        );
+
+        sk_r + partial.s * num * den.invert().unwrap()
+    });
+
+    if &JubJubExtended::from(GENERATOR).mul(&sk_r) != note.pk_r() {
+        utils::wipe(&mut sk_r);
+        return Err(Error::InvalidShare);
+    }
+
+    // With `a = 0`, `H(a · R)` is the hash of the identity, so `b` is chosen for the key to derive
+    // `sk_r` for this note
+    let identity = note::shared_secret_hashes(&JubJubExtended::identity())[0];
+    let sk = SecretKey::new(JubJubScalar::zero(), sk_r - identity);
+    utils::wipe(&mut sk_r);
+
+    let nullifier = note.generate_nullifier(&sk);
+    let asset = note.asset(Some(vk))?;
+    let value = note.value(Some(vk))?;
+    let blinding_factor = note.blinding_factor(Some(vk))?;
+    let merkle_root = *merkle_opening.root();
+
+    Ok(TransactionInput::new(
+        note.into(),
+        nullifier,
+        asset,
+        value,
+        blinding_factor,
+        sk,
+        merkle_opening,
+        merkle_root,
+    ))
+}
@@ -12,21 +12,37 @@ use unprolix::{Constructor, Getters, Setters};
 ///
 /// The notes are encrypted against secret a, so this is used to decrypt the blinding factor and
 /// value
-#[derive(Debug, Clone, Copy, Constructor, Getters, Setters)]
+///
+/// The secret a is wiped from memory when the key is dropped
+#[derive(Clone, Constructor, Getters, Setters)]
 pub struct ViewKey {
     a: JubJubScalar,
     B: JubJubExtended,
 }
 
+impl Drop for ViewKey {
+    fn drop(&mut self) {
+        utils::wipe(&mut self.a);
+    }
+}
+
+impl fmt::Debug for ViewKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ViewKey")
+            .field("B", &JubJubAffine::from(self.B))
+            .finish()
+    }
+}
+
 impl ConstantTimeEq for ViewKey {
     fn ct_eq(&self, other: &Self) -> Choice {
-        self.B.ct_eq(&other.B)
+        self.a.ct_eq(&other.a) & self.B.ct_eq(&other.B)
     }
 }
 
 impl PartialEq for ViewKey {
     fn eq(&self, other: &Self) -> bool {
-        self.a == other.a && self.ct_eq(&other).unwrap_u8() == 1
+        self.ct_eq(&other).unwrap_u8() == 1
     }
 }
 
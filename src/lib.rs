@@ -1,3 +1,22 @@
+//! Phoenix notes, keys and transactions
+//!
+//! # Constant-time paths
+//!
+//! Every comparison involving secret data is performed with [`subtle::ConstantTimeEq`], and the
+//! [`PartialEq`] implementations of the types below delegate to it without short-circuiting on
+//! the secret fields:
+//!
+//! * [`SecretKey`]: the scalars `a` and `b`
+//! * [`ViewKey`]: the scalar `a`, and the point `B`
+//! * [`PublicKey`]: the points `A` and `B`
+//! * [`ExtendedSecretKey`]: the secret and the chain code
+//! * [`Mnemonic`]: the entropy
+//! * [`TransactionInput`]: the asset, the value, the blinding factor and the secret key
+//! * [`TransactionOutput`]: the asset, the value and the blinding factor
+//!
+//! The note decryption is authenticated by the secretbox, and the point multiplications are
+//! performed by the constant-time implementations of `jubjub`.
+
 #![allow(non_snake_case)]
 #![feature(maybe_uninit_extra)]
 
@@ -6,10 +25,16 @@ pub use jubjub::Fr as JubJubScalar;
 pub use jubjub::{AffinePoint as JubJubAffine, ExtendedPoint as JubJubExtended};
 
 pub use crypto::MerkleProofProvider;
-pub use db::{NotesDb, NotesIter};
+pub use db::{DbEvent, NotesDb, NotesIter};
 pub use error::Error;
-pub use keys::{PublicKey, SecretKey, ViewKey};
-pub use note::{Note, NoteGenerator, NoteVariant, Nullifier, ObfuscatedNote, TransparentNote};
+pub use keys::{
+    Address, ExtendedSecretKey, Mnemonic, Network, PublicKey, SecretKey, SubaddressTable, ViewKey,
+};
+pub use mempool::{Mempool, MempoolConfig};
+pub use note::{
+    Note, NoteGenerator, NoteVariant, Nullifier, ObfuscatedNote, TransparentNote,
+    ENCRYPTED_MEMO_SIZE, MEMO_SIZE, NATIVE_ASSET,
+};
 pub use rpc::NoteType;
 pub use sodiumoxide::crypto::secretbox::xsalsa20poly1305::{Nonce, NONCEBYTES};
 pub use transaction::{
@@ -17,15 +42,22 @@ pub use transaction::{
     MAX_INPUT_NOTES_PER_TRANSACTION, MAX_NOTES_PER_TRANSACTION, MAX_OUTPUT_NOTES_PER_TRANSACTION,
     TX_SERIALIZED_SIZE,
 };
+pub use wallet::{TransactionTemplate, WatchOnlyWallet};
 
 /// Crypto primitives
 pub mod crypto;
 /// Storage implementation
 pub mod db;
+/// Serde implementations of the keys, notes and transactions, with hex encodings of the points
+/// and scalars. Enabled by the `serde-support` feature
+#[cfg(feature = "serde-support")]
+mod encoding;
 /// General error for phoenix operations
 pub mod error;
 /// Secret, view and public keys defition
 pub mod keys;
+/// Pool of transactions pending inclusion in a block
+pub mod mempool;
 /// Transparent and obfuscated notes defition
 pub mod note;
 /// RPC data generated via protobuf
@@ -34,5 +66,7 @@ pub mod rpc;
 pub mod transaction;
 /// General toolkit
 pub mod utils;
+/// Watch-only wallet built on a view key
+pub mod wallet;
 /// ZK Gadgets and value proof
 pub mod zk;
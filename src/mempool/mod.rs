@@ -0,0 +1,351 @@
+use crate::{db, BlsScalar, Error, Nullifier, Transaction, TransactionItem, TX_SERIALIZED_SIZE};
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Duration, Instant};
+
+use kelvin::ByteHash;
+use tracing::trace;
+
+#[cfg(test)]
+mod tests;
+
+/// Default maximum number of pending transactions
+pub const DEFAULT_MAX_COUNT: usize = 4096;
+/// Default maximum serialized size of the pending transactions
+pub const DEFAULT_MAX_BYTES: usize = DEFAULT_MAX_COUNT * TX_SERIALIZED_SIZE;
+/// Default time a pending transaction is kept before it expires
+pub const DEFAULT_TTL: Duration = Duration::from_secs(3600);
+
+/// Limits of the [`Mempool`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MempoolConfig {
+    /// Maximum serialized size, in bytes, of the pending transactions
+    pub max_bytes: usize,
+    /// Maximum number of pending transactions
+    pub max_count: usize,
+    /// Time a pending transaction is kept before it expires
+    pub ttl: Duration,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        MempoolConfig {
+            max_bytes: DEFAULT_MAX_BYTES,
+            max_count: DEFAULT_MAX_COUNT,
+            ttl: DEFAULT_TTL,
+        }
+    }
+}
+
+struct MempoolEntry {
+    transaction: Transaction,
+    size: usize,
+    inserted: Instant,
+}
+
+/// Set of verified transactions pending inclusion in a block
+///
+/// Every nullifier of the pending transactions is indexed, so a transaction that attempts to
+/// spend a note already spent in the state, or already pending in the pool, is rejected.
+///
+/// The pool is bounded by a [`MempoolConfig`]. When it is full, the transactions with the lowest
+/// fee are evicted first.
+#[derive(Default)]
+pub struct Mempool {
+    config: MempoolConfig,
+    size: usize,
+    entries: BTreeMap<BlsScalar, MempoolEntry>,
+    nullifiers: BTreeMap<Nullifier, BlsScalar>,
+}
+
+impl Mempool {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Create a new mempool bounded by the provided limits
+    pub fn with_config(config: MempoolConfig) -> Self {
+        Mempool {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Limits of the mempool
+    pub fn config(&self) -> &MempoolConfig {
+        &self.config
+    }
+
+    /// Verify and append a transaction to the pool. Return the hash of the transaction.
+    ///
+    /// The transaction is rejected if any of its nullifiers is spent on the provided state or
+    /// pending on the pool.
+    ///
+    /// The transaction is accounted with its serialized size, and `now` is its insertion time.
+    /// The transactions expired at `now` are removed first.
+    ///
+    /// If the pool is full, the pending transactions with a fee lower than the provided one are
+    /// evicted to make room for it. If that is not enough, the transaction is rejected.
+    pub fn push<H: ByteHash>(
+        &mut self,
+        db: &db::Db<H>,
+        mut transaction: Transaction,
+        now: Instant,
+    ) -> Result<BlsScalar, Error> {
+        self.expire(now);
+
+        let hash = transaction.hash();
+        if self.entries.contains_key(&hash) {
+            return Err(Error::DuplicatedTransaction);
+        }
+
+        let size = transaction.serialized_size()?;
+        if size > self.config.max_bytes {
+            return Err(Error::TransactionTooLarge);
+        }
+
+        self.check_nullifiers(db, &transaction)?;
+        let evicted = self.eviction_candidates(transaction.fee().value(), size)?;
+        transaction.verify()?;
+
+        evicted.iter().for_each(|h| {
+            trace!("Evicting tx {:?} from the full mempool", h);
+            self.remove(h);
+        });
+
+        trace!("Pushing tx {} to the mempool", transaction);
+        transaction.inputs().iter().for_each(|i| {
+            self.nullifiers.insert(*i.nullifier(), hash);
+        });
+
+        self.size += size;
+        self.entries.insert(
+            hash,
+            MempoolEntry {
+                transaction,
+                size,
+                inserted: now,
+            },
+        );
+
+        Ok(hash)
+    }
+
+    fn check_nullifiers<H: ByteHash>(
+        &self,
+        db: &db::Db<H>,
+        transaction: &Transaction,
+    ) -> Result<(), Error> {
+        let inputs = transaction.inputs();
+
+        inputs.iter().enumerate().try_for_each(|(idx, i)| {
+            let n = i.nullifier();
+
+            let duplicated = inputs[..idx].iter().any(|p| p.nullifier() == n);
+            if duplicated || self.nullifiers.contains_key(n) || db.fetch_nullifier(n)?.is_some() {
+                return Err(Error::DoubleSpending);
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Return the lowest fee transactions that must be evicted so a new transaction with the
+    /// provided fee and size fits the pool
+    fn eviction_candidates(&self, fee: u64, size: usize) -> Result<Vec<BlsScalar>, Error> {
+        let mut lowest: Vec<(u64, &BlsScalar, &MempoolEntry)> = self
+            .entries
+            .iter()
+            .map(|(h, e)| (e.transaction.fee().value(), h, e))
+            .collect();
+        lowest.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut count = self.entries.len();
+        let mut bytes = self.size;
+        let mut evicted = vec![];
+
+        for (f, h, e) in lowest {
+            if count < self.config.max_count && bytes + size <= self.config.max_bytes {
+                break;
+            }
+
+            if f >= fee {
+                return Err(Error::MempoolFull);
+            }
+
+            count -= 1;
+            bytes -= e.size;
+            evicted.push(*h);
+        }
+
+        if count < self.config.max_count && bytes + size <= self.config.max_bytes {
+            Ok(evicted)
+        } else {
+            Err(Error::MempoolFull)
+        }
+    }
+
+    /// Remove the transactions that, at `now`, are pending for longer than the configured
+    /// time-to-live. Return the expired transactions.
+    pub fn expire(&mut self, now: Instant) -> Vec<Transaction> {
+        let ttl = self.config.ttl;
+        let expired: Vec<BlsScalar> = self
+            .entries
+            .iter()
+            .filter(|(_, e)| now.saturating_duration_since(e.inserted) >= ttl)
+            .map(|(h, _)| *h)
+            .collect();
+
+        expired
+            .iter()
+            .filter_map(|h| {
+                trace!("Expiring tx {:?} from the mempool", h);
+                self.remove(h)
+            })
+            .collect()
+    }
+
+    /// Fetch a pending transaction provided its hash
+    pub fn get(&self, hash: &BlsScalar) -> Option<&Transaction> {
+        self.entries.get(hash).map(|e| &e.transaction)
+    }
+
+    /// Return the hash of the pending transaction that spends the provided nullifier, if any
+    pub fn fetch_nullifier(&self, nullifier: &Nullifier) -> Option<&BlsScalar> {
+        self.nullifiers.get(nullifier)
+    }
+
+    /// Remove a pending transaction provided its hash
+    pub fn remove(&mut self, hash: &BlsScalar) -> Option<Transaction> {
+        let entry = self.entries.remove(hash)?;
+        let transaction = entry.transaction;
+
+        self.size -= entry.size;
+        transaction.inputs().iter().for_each(|i| {
+            self.nullifiers.remove(i.nullifier());
+        });
+
+        Some(transaction)
+    }
+
+    /// Evict the pending transactions included in a block, or that conflict with the nullifiers
+    /// it spends. Return the evicted transactions.
+    pub fn apply_block(&mut self, block: &[Transaction]) -> Vec<Transaction> {
+        let mut evicted = vec![];
+
+        for t in block {
+            let conflicts: Vec<BlsScalar> = t
+                .inputs()
+                .iter()
+                .filter_map(|i| self.nullifiers.get(i.nullifier()).copied())
+                .chain(Some(t.hash()))
+                .collect();
+
+            for hash in conflicts {
+                if let Some(tx) = self.remove(&hash) {
+                    trace!("Evicting tx {} from the mempool", tx);
+                    evicted.push(tx);
+                }
+            }
+        }
+
+        evicted
+    }
+
+    /// Select the pending transactions to be included in a block, prioritised by fee value.
+    ///
+    /// The transactions are accounted with their serialized size. A transaction that doesn't fit
+    /// in the remaining `max_bytes` is skipped, so smaller ones with a lower fee can still fill
+    /// the block. Transactions whose nullifiers clash with an already selected one are skipped,
+    /// so the result is guaranteed to apply cleanly to the provided state, in the returned order.
+    ///
+    /// Transactions expired at `now`, and transactions with an input declaring an unlock height
+    /// above `height`, are not selected.
+    pub fn select<H: ByteHash>(
+        &self,
+        db: &db::Db<H>,
+        height: u64,
+        now: Instant,
+        max_bytes: usize,
+    ) -> Vec<&Transaction> {
+        let ttl = self.config.ttl;
+        let mut candidates: Vec<&MempoolEntry> = self
+            .entries
+            .values()
+            .filter(|e| now.saturating_duration_since(e.inserted) < ttl)
+            .collect();
+        candidates.sort_by(|a, b| {
+            b.transaction
+                .fee()
+                .value()
+                .cmp(&a.transaction.fee().value())
+        });
+
+        let mut spent = BTreeSet::new();
+        let mut size = 0;
+        let mut selected = vec![];
+
+        for entry in candidates {
+            let tx = &entry.transaction;
+            if size + entry.size > max_bytes {
+                continue;
+            }
+
+            if let Err(e) = Self::check_selection(db, &spent, tx, height) {
+                trace!("Skipping tx {} from the block selection: {}", tx, e);
+                continue;
+            }
+
+            spent.extend(tx.inputs().iter().map(|i| *i.nullifier()));
+            size += entry.size;
+            selected.push(tx);
+        }
+
+        selected
+    }
+
+    /// Check that a transaction can be stored on the provided state after the transactions that
+    /// spent the provided nullifiers
+    fn check_selection<H: ByteHash>(
+        db: &db::Db<H>,
+        spent: &BTreeSet<Nullifier>,
+        transaction: &Transaction,
+        height: u64,
+    ) -> Result<(), Error> {
+        let inputs = transaction.inputs();
+
+        inputs.iter().enumerate().try_for_each(|(idx, i)| {
+            if i.unlock_height > height {
+                return Err(Error::NoteLocked);
+            }
+
+            let n = i.nullifier();
+            let duplicated = inputs[..idx].iter().any(|p| p.nullifier() == n);
+            if duplicated || spent.contains(n) || db.fetch_nullifier(n)?.is_some() {
+                return Err(Error::DoubleSpending);
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Iterate over the pending transactions
+    pub fn iter(&self) -> impl Iterator<Item = &Transaction> {
+        self.entries.values().map(|e| &e.transaction)
+    }
+
+    /// Number of pending transactions
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Return true if there are no pending transactions
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Serialized size, in bytes, of the pending transactions
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
@@ -0,0 +1,342 @@
+use crate::{
+    db, Error, Mempool, MempoolConfig, MerkleProofProvider, Note, NoteGenerator, NoteVariant,
+    ObfuscatedNote, SecretKey, Transaction, TransactionItem, TransparentNote, TX_SERIALIZED_SIZE,
+};
+
+use std::time::{Duration, Instant};
+
+use kelvin::Blake2b;
+
+fn unspent_note(db: &mut db::Db<Blake2b>, sk: &SecretKey, value: u64) -> NoteVariant {
+    let pk = sk.public_key();
+    let note: NoteVariant = TransparentNote::output(&pk, value).0.into();
+
+    let idx = db.store_unspent_note(note).unwrap();
+    db.fetch_note(idx).unwrap()
+}
+
+fn spend(db: &db::Db<Blake2b>, note: NoteVariant, sk: &SecretKey, fee: u64) -> Transaction {
+    spend_to::<ObfuscatedNote>(db, note, sk, fee)
+}
+
+fn spend_to<N: NoteGenerator>(
+    db: &db::Db<Blake2b>,
+    note: NoteVariant,
+    sk: &SecretKey,
+    fee: u64,
+) -> Transaction {
+    let mut tx = Transaction::default();
+
+    let value = note.value(Some(&sk.view_key())).unwrap();
+    let merkle_opening = db.opening(&note).unwrap();
+    tx.push_input(
+        note.to_transaction_input(merkle_opening, sk.clone())
+            .unwrap(),
+    )
+    .unwrap();
+
+    let pk = SecretKey::default().public_key();
+    let (output, blinding_factor) = N::output(&pk, value - fee);
+    tx.push_output(output.to_transaction_output(value - fee, blinding_factor, pk))
+        .unwrap();
+
+    let pk = SecretKey::default().public_key();
+    let (output, blinding_factor) = TransparentNote::output(&pk, fee);
+    tx.set_fee(output.to_transaction_output(fee, blinding_factor, pk));
+
+    tx.prove().unwrap();
+
+    tx
+}
+
+#[test]
+fn push_and_fetch() {
+    let mut db = db::Db::<Blake2b>::default();
+    let mut mempool = Mempool::new();
+
+    let sk = SecretKey::default();
+    let note = unspent_note(&mut db, &sk, 100);
+    let tx = spend(&db, note, &sk, 3);
+    let tx_hash = tx.hash();
+    let nullifier = *tx.inputs()[0].nullifier();
+
+    let hash = mempool.push(&db, tx, Instant::now()).unwrap();
+
+    assert_eq!(tx_hash, hash);
+    assert_eq!(1, mempool.len());
+    assert_eq!(tx_hash, mempool.get(&hash).unwrap().hash());
+    assert_eq!(Some(&hash), mempool.fetch_nullifier(&nullifier));
+
+    assert!(mempool.remove(&hash).is_some());
+    assert!(mempool.is_empty());
+    assert!(mempool.fetch_nullifier(&nullifier).is_none());
+}
+
+#[test]
+fn pending_double_spending() {
+    let mut db = db::Db::<Blake2b>::default();
+    let mut mempool = Mempool::new();
+
+    let sk = SecretKey::default();
+    let note = unspent_note(&mut db, &sk, 100);
+    let tx = spend(&db, note, &sk, 3);
+    let tx_double_spending = spend(&db, note, &sk, 5);
+
+    mempool.push(&db, tx, Instant::now()).unwrap();
+
+    match mempool.push(&db, tx_double_spending, Instant::now()) {
+        Err(Error::DoubleSpending) => (),
+        _ => panic!("A pending nullifier must be rejected"),
+    }
+    assert_eq!(1, mempool.len());
+}
+
+#[test]
+fn state_double_spending() {
+    let mut db = db::Db::<Blake2b>::default();
+    let mut mempool = Mempool::new();
+
+    let sk = SecretKey::default();
+    let note = unspent_note(&mut db, &sk, 100);
+    let tx = spend(&db, note, &sk, 3);
+    let tx_double_spending = spend(&db, note, &sk, 5);
+
+    db.store_transaction(&tx, 0).unwrap();
+
+    match mempool.push(&db, tx_double_spending, Instant::now()) {
+        Err(Error::DoubleSpending) => (),
+        _ => panic!("A spent nullifier must be rejected"),
+    }
+    assert!(mempool.is_empty());
+}
+
+#[test]
+fn apply_block_evicts_conflicts() {
+    let mut db = db::Db::<Blake2b>::default();
+    let mut mempool = Mempool::new();
+
+    let sk = SecretKey::default();
+    let note = unspent_note(&mut db, &sk, 100);
+    let tx = spend(&db, note, &sk, 3);
+    let tx_hash = tx.hash();
+    let tx_block = spend(&db, note, &sk, 5);
+
+    let sk = SecretKey::default();
+    let note = unspent_note(&mut db, &sk, 50);
+    let tx_pending = spend(&db, note, &sk, 2);
+    let tx_pending_hash = tx_pending.hash();
+
+    mempool.push(&db, tx, Instant::now()).unwrap();
+    mempool.push(&db, tx_pending, Instant::now()).unwrap();
+
+    db.store_transaction(&tx_block, 0).unwrap();
+    let evicted = mempool.apply_block(&[tx_block]);
+
+    assert_eq!(1, evicted.len());
+    assert_eq!(tx_hash, evicted[0].hash());
+    assert!(mempool.get(&tx_hash).is_none());
+    assert!(mempool.get(&tx_pending_hash).is_some());
+}
+
+#[test]
+fn select_by_fee() {
+    let mut db = db::Db::<Blake2b>::default();
+    let mut mempool = Mempool::new();
+
+    let mut size = 0;
+    for fee in &[3, 8, 5] {
+        let sk = SecretKey::default();
+        let note = unspent_note(&mut db, &sk, 100);
+        let tx = spend(&db, note, &sk, *fee);
+        size = tx.serialized_size().unwrap();
+        mempool.push(&db, tx, Instant::now()).unwrap();
+    }
+
+    let selected = mempool.select(&db, 0, Instant::now(), 2 * size + 1);
+    let fees: Vec<u64> = selected.iter().map(|tx| tx.fee().value()).collect();
+    assert_eq!(vec![8, 5], fees);
+
+    let selected = mempool.select(&db, 0, Instant::now(), size - 1);
+    assert!(selected.is_empty());
+}
+
+#[test]
+fn select_fills_with_smaller() {
+    let mut db = db::Db::<Blake2b>::default();
+    let mut mempool = Mempool::new();
+
+    let sk = SecretKey::default();
+    let note = unspent_note(&mut db, &sk, 100);
+    let large = spend(&db, note, &sk, 9);
+    let large_size = large.serialized_size().unwrap();
+    mempool.push(&db, large, Instant::now()).unwrap();
+
+    let sk = SecretKey::default();
+    let note = unspent_note(&mut db, &sk, 100);
+    mempool
+        .push(&db, spend(&db, note, &sk, 5), Instant::now())
+        .unwrap();
+
+    let sk = SecretKey::default();
+    let note = unspent_note(&mut db, &sk, 100);
+    let small = spend_to::<TransparentNote>(&db, note, &sk, 2);
+    let small_size = small.serialized_size().unwrap();
+    assert!(small_size < large_size);
+    mempool.push(&db, small, Instant::now()).unwrap();
+
+    // The second transaction doesn't fit after the first, but the third does
+    let selected = mempool.select(&db, 0, Instant::now(), large_size + small_size);
+    let fees: Vec<u64> = selected.iter().map(|tx| tx.fee().value()).collect();
+    assert_eq!(vec![9, 2], fees);
+}
+
+#[test]
+fn select_skips_conflicts() {
+    let mut db = db::Db::<Blake2b>::default();
+    let mut mempool = Mempool::new();
+
+    let sk = SecretKey::default();
+    let note = unspent_note(&mut db, &sk, 100);
+    let tx_conflict = spend(&db, note, &sk, 1);
+    mempool
+        .push(&db, spend(&db, note, &sk, 9), Instant::now())
+        .unwrap();
+
+    let sk = SecretKey::default();
+    let note = unspent_note(&mut db, &sk, 100);
+    mempool
+        .push(&db, spend(&db, note, &sk, 4), Instant::now())
+        .unwrap();
+
+    // The state moved forward without the block being applied to the mempool
+    db.store_transaction(&tx_conflict, 0).unwrap();
+
+    let selected = mempool.select(&db, 0, Instant::now(), 10 * TX_SERIALIZED_SIZE);
+    assert_eq!(1, selected.len());
+    assert_eq!(4, selected[0].fee().value());
+
+    selected
+        .into_iter()
+        .for_each(|tx| assert!(db.store_transaction(tx, 0).is_ok()));
+}
+
+#[test]
+fn evict_lowest_fee() {
+    let mut db = db::Db::<Blake2b>::default();
+
+    let mut config = MempoolConfig::default();
+    config.max_count = 2;
+    let mut mempool = Mempool::with_config(config);
+
+    let mut hashes = vec![];
+    let mut sizes = vec![];
+    for fee in &[3, 5] {
+        let sk = SecretKey::default();
+        let note = unspent_note(&mut db, &sk, 100);
+        let tx = spend(&db, note, &sk, *fee);
+        sizes.push(tx.serialized_size().unwrap());
+        hashes.push(mempool.push(&db, tx, Instant::now()).unwrap());
+    }
+
+    let sk = SecretKey::default();
+    let note = unspent_note(&mut db, &sk, 100);
+    match mempool.push(&db, spend(&db, note, &sk, 3), Instant::now()) {
+        Err(Error::MempoolFull) => (),
+        _ => panic!("A full mempool must reject a transaction with a lower fee"),
+    }
+
+    let sk = SecretKey::default();
+    let note = unspent_note(&mut db, &sk, 100);
+    let tx = spend(&db, note, &sk, 8);
+    let size = tx.serialized_size().unwrap();
+    let hash = mempool.push(&db, tx, Instant::now()).unwrap();
+
+    assert_eq!(2, mempool.len());
+    assert_eq!(sizes[1] + size, mempool.size());
+    assert!(mempool.get(&hashes[0]).is_none());
+    assert!(mempool.get(&hashes[1]).is_some());
+    assert!(mempool.get(&hash).is_some());
+}
+
+#[test]
+fn reject_too_large() {
+    let mut db = db::Db::<Blake2b>::default();
+
+    let sk = SecretKey::default();
+    let note = unspent_note(&mut db, &sk, 100);
+    let tx = spend(&db, note, &sk, 3);
+
+    let mut config = MempoolConfig::default();
+    config.max_bytes = tx.serialized_size().unwrap() - 1;
+    let mut mempool = Mempool::with_config(config);
+
+    match mempool.push(&db, tx, Instant::now()) {
+        Err(Error::TransactionTooLarge) => (),
+        _ => panic!("A transaction bigger than the mempool must be rejected"),
+    }
+}
+
+#[test]
+fn reject_zero_count() {
+    let mut db = db::Db::<Blake2b>::default();
+
+    let mut config = MempoolConfig::default();
+    config.max_count = 0;
+    let mut mempool = Mempool::with_config(config);
+
+    let sk = SecretKey::default();
+    let note = unspent_note(&mut db, &sk, 100);
+    match mempool.push(&db, spend(&db, note, &sk, 3), Instant::now()) {
+        Err(Error::MempoolFull) => (),
+        _ => panic!("A mempool without room must be full"),
+    }
+}
+
+#[test]
+fn expire_after_ttl() {
+    let mut db = db::Db::<Blake2b>::default();
+
+    let mut config = MempoolConfig::default();
+    config.ttl = Duration::from_secs(60);
+    let mut mempool = Mempool::with_config(config);
+
+    let sk = SecretKey::default();
+    let note = unspent_note(&mut db, &sk, 100);
+    let now = Instant::now();
+    let hash = mempool.push(&db, spend(&db, note, &sk, 3), now).unwrap();
+
+    assert!(mempool.expire(now).is_empty());
+    assert_eq!(1, mempool.select(&db, 0, now, TX_SERIALIZED_SIZE).len());
+
+    let later = now + config.ttl;
+    assert!(mempool.select(&db, 0, later, TX_SERIALIZED_SIZE).is_empty());
+    let expired = mempool.expire(later);
+    assert_eq!(1, expired.len());
+    assert_eq!(hash, expired[0].hash());
+    assert!(mempool.is_empty());
+    assert_eq!(0, mempool.size());
+}
+
+#[test]
+fn push_expires_pending() {
+    let mut db = db::Db::<Blake2b>::default();
+
+    let mut config = MempoolConfig::default();
+    config.ttl = Duration::from_secs(60);
+    let mut mempool = Mempool::with_config(config);
+
+    let now = Instant::now();
+    let sk = SecretKey::default();
+    let note = unspent_note(&mut db, &sk, 100);
+    let expired = mempool.push(&db, spend(&db, note, &sk, 3), now).unwrap();
+
+    let sk = SecretKey::default();
+    let note = unspent_note(&mut db, &sk, 100);
+    let tx = spend(&db, note, &sk, 3);
+    let size = tx.serialized_size().unwrap();
+    let hash = mempool.push(&db, tx, now + config.ttl).unwrap();
+
+    assert!(mempool.get(&expired).is_none());
+    assert!(mempool.get(&hash).is_some());
+    assert_eq!(size, mempool.size());
+}
@@ -1,6 +1,6 @@
 use crate::{
     crypto, rpc, utils, BlsScalar, Error, JubJubAffine, JubJubExtended, JubJubScalar, Nonce,
-    NoteType, PublicKey, SecretKey, TransactionInput, TransactionOutput, ViewKey,
+    NoteType, PublicKey, SecretKey, SubaddressTable, TransactionInput, TransactionOutput, ViewKey,
 };
 
 use rand;
@@ -28,6 +28,13 @@ pub use obfuscated::ObfuscatedNote;
 pub use transparent::TransparentNote;
 pub use variant::NoteVariant;
 
+/// Size of the memo attached to a note
+pub const MEMO_SIZE: usize = 32;
+/// Size of the encrypted memo, including the authentication tag
+pub const ENCRYPTED_MEMO_SIZE: usize = MEMO_SIZE + 16;
+/// Asset id of the native token, committed with the jubjub generator
+pub const NATIVE_ASSET: u64 = 0;
+
 /// Trait for the notes construction
 pub trait NoteGenerator:
     Sized
@@ -38,22 +45,95 @@ pub trait NoteGenerator:
     + Into<NoteVariant>
     + TryFrom<NoteVariant>
 {
+    /// Create a new phoenix output note of the provided asset with a memo, without inner
+    /// randomness
+    ///
+    /// The value is committed with the generator of the asset, see [`crypto::asset_generator`]
+    fn deterministic_output_with_asset(
+        r: &JubJubScalar,
+        nonce: Nonce,
+        pk: &PublicKey,
+        asset: u64,
+        value: u64,
+        blinding_factor: JubJubScalar,
+        memo: &[u8; MEMO_SIZE],
+    ) -> Self;
+
+    /// Create a new phoenix output note with a memo, without inner randomness
+    fn deterministic_output_with_memo(
+        r: &JubJubScalar,
+        nonce: Nonce,
+        pk: &PublicKey,
+        value: u64,
+        blinding_factor: JubJubScalar,
+        memo: &[u8; MEMO_SIZE],
+    ) -> Self {
+        Self::deterministic_output_with_asset(
+            r,
+            nonce,
+            pk,
+            NATIVE_ASSET,
+            value,
+            blinding_factor,
+            memo,
+        )
+    }
+
     /// Create a new phoenix output note without inner randomness
+    ///
+    /// The note carries an empty memo, so it can't be told apart from a note with a memo
     fn deterministic_output(
         r: &JubJubScalar,
         nonce: Nonce,
         pk: &PublicKey,
         value: u64,
         blinding_factor: JubJubScalar,
-    ) -> Self;
+    ) -> Self {
+        Self::deterministic_output_with_memo(
+            r,
+            nonce,
+            pk,
+            value,
+            blinding_factor,
+            &[0x00u8; MEMO_SIZE],
+        )
+    }
 
     /// Create a new phoenix output note
     fn output(pk: &PublicKey, value: u64) -> (Self, JubJubScalar) {
+        Self::output_with_memo(pk, value, &[0x00u8; MEMO_SIZE])
+    }
+
+    /// Create a new phoenix output note with a memo readable by the [`ViewKey`] of the receiver,
+    /// such as an invoice id
+    fn output_with_memo(
+        pk: &PublicKey,
+        value: u64,
+        memo: &[u8; MEMO_SIZE],
+    ) -> (Self, JubJubScalar) {
+        Self::output_with_asset(pk, NATIVE_ASSET, value, memo)
+    }
+
+    /// Create a new phoenix output note of the provided asset
+    fn output_with_asset(
+        pk: &PublicKey,
+        asset: u64,
+        value: u64,
+        memo: &[u8; MEMO_SIZE],
+    ) -> (Self, JubJubScalar) {
         let r = utils::gen_random_scalar();
         let nonce = utils::gen_nonce();
         let blinding_factor = utils::gen_random_scalar();
 
-        let note = Self::deterministic_output(&r, nonce, pk, value, blinding_factor);
+        let note = Self::deterministic_output_with_asset(
+            &r,
+            nonce,
+            pk,
+            asset,
+            value,
+            blinding_factor,
+            memo,
+        );
 
         (note, blinding_factor)
     }
@@ -68,7 +148,8 @@ pub trait NoteGenerator:
         let vk = sk.view_key();
 
         let nullifier = self.generate_nullifier(&sk);
-        let value = self.value(Some(&vk));
+        let asset = self.asset(Some(&vk))?;
+        let value = self.value(Some(&vk))?;
         let blinding_factor = self.blinding_factor(Some(&vk))?;
 
         let merkle_root = *merkle_opening.root();
@@ -76,6 +157,7 @@ pub trait NoteGenerator:
         Ok(TransactionInput::new(
             self.into(),
             nullifier,
+            asset,
             value,
             blinding_factor,
             sk,
@@ -87,14 +169,27 @@ pub trait NoteGenerator:
     /// Create a new transaction output item provided the target value, blinding factor and pk for
     /// the proof construction.
     ///
-    /// The parameters are not present on the note; hence they need to be provided.
+    /// The parameters are not present on the note; hence they need to be provided. The note is
+    /// expected to be of the [`NATIVE_ASSET`]
     fn to_transaction_output(
         self,
         value: u64,
         blinding_factor: JubJubScalar,
         pk: PublicKey,
     ) -> TransactionOutput {
-        TransactionOutput::new(self.into(), value, blinding_factor, pk)
+        self.to_transaction_output_with_asset(NATIVE_ASSET, value, blinding_factor, pk)
+    }
+
+    /// Create a new transaction output item of the provided asset, for notes created with
+    /// [`NoteGenerator::output_with_asset`]
+    fn to_transaction_output_with_asset(
+        self,
+        asset: u64,
+        value: u64,
+        blinding_factor: JubJubScalar,
+        pk: PublicKey,
+    ) -> TransactionOutput {
+        TransactionOutput::new(self.into(), asset, value, blinding_factor, pk)
     }
 
     /// Generate a random `r` and call [`Self::new_pk_r`]
@@ -111,7 +206,7 @@ pub trait NoteGenerator:
         let R = JubJubExtended::from(GENERATOR).mul(r);
 
         let rA = pk.A().mul(r);
-        let rA = crypto::hash_jubjub_projective_to_jubjub_scalar(&rA);
+        let rA = crypto::hash_to_jubjub_scalar(&rA);
         let rA = JubJubExtended::from(GENERATOR).mul(rA);
 
         let pk_r = rA + pk.B();
@@ -139,6 +234,24 @@ pub trait NoteGenerator:
         let bytes = crypto::encrypt(r, pk, &nonce.increment_le(), blinding_factor_bytes);
         utils::safe_48_chunk(bytes.as_slice())
     }
+
+    /// Internally calls the [`crypto::encrypt`] to mask the memo
+    fn encrypt_memo(
+        r: &JubJubScalar,
+        pk: &PublicKey,
+        nonce: &Nonce,
+        memo: &[u8; MEMO_SIZE],
+    ) -> [u8; ENCRYPTED_MEMO_SIZE] {
+        let bytes = crypto::encrypt(r, pk, &nonce.increment_le().increment_le(), &memo[..]);
+        utils::safe_48_chunk(bytes.as_slice())
+    }
+
+    /// Internally calls the [`crypto::encrypt`] to mask the asset id
+    fn encrypt_asset(r: &JubJubScalar, pk: &PublicKey, nonce: &Nonce, asset: u64) -> [u8; 24] {
+        let nonce = nonce.increment_le().increment_le().increment_le();
+        let bytes = crypto::encrypt(r, pk, &nonce, &asset.to_le_bytes()[..]);
+        utils::safe_24_chunk(bytes.as_slice())
+    }
 }
 
 /// Phoenix note methods. Both transparent and obfuscated notes implements this
@@ -150,7 +263,7 @@ pub trait Note: Debug + Send + Sync + io::Read + io::Write {
 
         let idx = BlsScalar::from(self.idx());
 
-        crypto::sponge_hash(&[sk_r, idx]).into()
+        crypto::domain_hash(crypto::HashDomain::Nullifier, &[sk_r, idx]).into()
     }
 
     /// Fully decrypt the note (value and blinding factor) with the provided [`ViewKey`], and
@@ -158,7 +271,8 @@ pub trait Note: Debug + Send + Sync + io::Read + io::Write {
     fn rpc_decrypted_note(&self, vk: &ViewKey) -> Result<rpc::DecryptedNote, Error> {
         let note_type = self.note().into();
         let pos = self.idx();
-        let value = self.value(Some(vk));
+        let value = self.value(Some(vk))?;
+        let asset = self.asset(Some(vk))?;
         let nonce = Some((*self.nonce()).into());
         let r_g = Some((*self.R()).into());
         let pk_r = Some((*self.pk_r()).into());
@@ -186,6 +300,18 @@ pub trait Note: Debug + Send + Sync + io::Read + io::Write {
             .unwrap_or(rpc::decrypted_note::RawValue::TransparentValue(value));
         let raw_value = Some(raw_value);
 
+        let raw_asset = self
+            .encrypted_asset()
+            .map(|a| rpc::decrypted_note::RawAsset::EncryptedAsset(a.to_vec()))
+            .unwrap_or(rpc::decrypted_note::RawAsset::TransparentAsset(asset));
+        let raw_asset = Some(raw_asset);
+
+        // Anyone can read a transparent note, but only its receiver can read the memo. A memo that
+        // can't be decrypted is returned empty, as the note is still spendable
+        let memo = self.memo(vk).unwrap_or(None);
+        let memo = memo.map(|m| m.to_vec()).unwrap_or_default();
+        let encrypted_memo = self.encrypted_memo().to_vec();
+
         Ok(rpc::DecryptedNote {
             note_type,
             pos,
@@ -197,20 +323,36 @@ pub trait Note: Debug + Send + Sync + io::Read + io::Write {
             blinding_factor,
             raw_blinding_factor,
             raw_value,
+            encrypted_memo,
+            memo,
+            asset,
+            raw_asset,
+            unlock_height: self.unlock_height(),
         })
     }
 
-    /// Return a hash represented by `H(value_commitment, idx, H([R]), H([PKr]))`
+    /// Return a hash represented by
+    /// `H(value_commitment, idx, H([R]), H([PKr]), memo, unlock_height)`
+    ///
+    /// The encrypted memo and the unlock height are part of the hash, so they can't be replaced
+    /// without invalidating the note
     fn hash(&self) -> BlsScalar {
         let pk_r = JubJubAffine::from(self.pk_r());
-
-        crypto::sponge_hash(&[
-            self.value_commitment().get_x(),
-            self.value_commitment().get_y(),
-            BlsScalar::from(self.idx()),
-            pk_r.get_x(),
-            pk_r.get_y(),
-        ])
+        let memo = memo_scalars(self.encrypted_memo());
+
+        crypto::domain_hash(
+            crypto::HashDomain::Note,
+            &[
+                self.value_commitment().get_x(),
+                self.value_commitment().get_y(),
+                BlsScalar::from(self.idx()),
+                pk_r.get_x(),
+                pk_r.get_y(),
+                memo[0],
+                memo[1],
+                BlsScalar::from(self.unlock_height()),
+            ],
+        )
     }
 
     /// Return the type of the note
@@ -221,49 +363,148 @@ pub trait Note: Debug + Send + Sync + io::Read + io::Write {
     /// Set the position of the note on the tree. This, naturally, won't reflect immediatelly on
     /// the data storage
     fn set_idx(&mut self, idx: u64);
+    /// Return the block height from which the note can be spent. Zero for unlocked notes
+    fn unlock_height(&self) -> u64;
+    /// Lock the note until the provided block height, for vesting and escrow outputs. The height
+    /// is part of the hash, so it must be set before the note is added to a transaction
+    fn set_unlock_height(&mut self, unlock_height: u64);
+    /// Return true if the note can't be spent on a block of the provided height
+    fn is_locked(&self, height: u64) -> bool {
+        self.unlock_height() > height
+    }
     /// Nonce used for the encrypt / decrypt of data for this note
     fn nonce(&self) -> &Nonce;
 
     /// Attempt to decrypt the note value provided a [`ViewKey`]. Always succeeds for transparent
-    /// notes, and will return random values for obfuscated notes provided the wrong view key.
-    fn value(&self, vk: Option<&ViewKey>) -> u64;
+    /// notes, and will return [`Error::DecryptionFailed`] for obfuscated notes provided the wrong
+    /// view key.
+    fn value(&self, vk: Option<&ViewKey>) -> Result<u64, Error>;
 
     /// Return the raw encrypted bytes of the value. If the note is transparent, `None` is returned
     fn encrypted_value(&self) -> Option<&[u8; 24]>;
-    /// Return the value commitment `H(value, blinding_factor)`
+    /// Attempt to decrypt the asset id of the note, with the same rules of [`Note::value`]
+    fn asset(&self, vk: Option<&ViewKey>) -> Result<u64, Error>;
+    /// Return the raw encrypted bytes of the asset id. If the note is transparent, `None` is
+    /// returned
+    fn encrypted_asset(&self) -> Option<&[u8; 24]>;
+    /// Return the value commitment `value · G_asset + blinding_factor · H`
     fn value_commitment(&self) -> &JubJubExtended;
     /// Decrypt the blinding factor with the provided [`ViewKey`]
     ///
-    /// If the decrypt fails, a random value is returned
+    /// If the decrypt fails, [`Error::DecryptionFailed`] is returned
     fn blinding_factor(&self, vk: Option<&ViewKey>) -> Result<JubJubScalar, Error>;
     /// Return the raw encrypted value blinding factor
     fn encrypted_blinding_factor(&self) -> &[u8; 48];
+    /// Return the raw encrypted memo
+    fn encrypted_memo(&self) -> &[u8; ENCRYPTED_MEMO_SIZE];
+
+    /// Decrypt the memo with the provided [`ViewKey`]. An empty memo is returned as `None`
+    ///
+    /// If the decrypt fails, [`Error::DecryptionFailed`] is returned
+    fn memo(&self, vk: &ViewKey) -> Result<Option<[u8; MEMO_SIZE]>, Error> {
+        let nonce = self.nonce().increment_le().increment_le();
+        let memo = crypto::decrypt(self.R(), vk, &nonce, &self.encrypted_memo()[..])?;
+
+        if memo.len() != MEMO_SIZE {
+            return Err(Error::InvalidParameters);
+        }
+
+        let mut bytes = [0x00u8; MEMO_SIZE];
+        bytes.copy_from_slice(memo.as_slice());
+
+        Ok(Some(bytes).filter(|m| m != &[0x00u8; MEMO_SIZE]))
+    }
+
     /// Return the `r · G` used for the DHKE randomness
     fn R(&self) -> &JubJubExtended;
     /// Return the public DHKE combined with the secret key of the owner of the note
     fn pk_r(&self) -> &JubJubExtended;
 
     /// Generate a `sk_r = H(a · R) + b`
+    ///
+    /// If the note was created with the legacy hash, the legacy `H(a · R)` is used
     fn sk_r(&self, sk: &SecretKey) -> JubJubScalar {
         let aR = self.R().mul(sk.a());
-        let aR = crypto::hash_jubjub_projective_to_jubjub_scalar(&aR);
+        let B = JubJubExtended::from(GENERATOR).mul(sk.b());
+
+        let hashes = shared_secret_hashes(&aR);
+        let aR = hashes
+            .iter()
+            .find(|h| self.pk_r() == &(JubJubExtended::from(GENERATOR).mul(*h) + B))
+            .unwrap_or(&hashes[0]);
 
         aR + sk.b()
     }
 
+    /// Sign a message with the one-time secret `sk_r` of the note, proving its ownership without
+    /// revealing the secret key
+    fn sign(&self, sk: &SecretKey, msg: &[u8]) -> crypto::Signature {
+        crypto::Signature::sign(&self.sk_r(sk), msg)
+    }
+
+    /// Verify a signature created with [`Note::sign`] against `PKr`
+    fn verify_signature(&self, msg: &[u8], signature: &crypto::Signature) -> bool {
+        signature.verify(self.pk_r(), msg)
+    }
+
     /// Return true if the note was constructed with the same secret that constructed the provided
     /// view key
     ///
-    /// This holds true if `H(a · R) + B == PKr`
+    /// This holds true if `H(a · R) + B == PKr`, with either the current or the legacy hash
     fn is_owned_by(&self, vk: &ViewKey) -> bool {
         let aR = self.R().mul(vk.a());
-        let aR = crypto::hash_jubjub_projective_to_jubjub_scalar(&aR);
-        let aR = JubJubExtended::from(GENERATOR).mul(&aR);
 
-        let pk_r = aR + vk.B();
+        shared_secret_hashes(&aR).iter().any(|aR| {
+            let pk_r = JubJubExtended::from(GENERATOR).mul(aR) + vk.B();
 
-        self.pk_r() == &pk_r
+            self.pk_r() == &pk_r
+        })
     }
+
+    /// Return the index of the subaddress of the table the note was sent to, if any
+    ///
+    /// This recovers `B_i = PKr - H(a · R) · G` and looks it up on the table
+    fn subaddress_index(&self, table: &SubaddressTable) -> Option<u32> {
+        let aR = self.R().mul(table.view_key().a());
+
+        shared_secret_hashes(&aR).iter().find_map(|aR| {
+            let aR = JubJubExtended::from(GENERATOR).mul(aR);
+
+            table.lookup(&(self.pk_r() - aR))
+        })
+    }
+}
+
+/// Split the encrypted memo in field elements of 31 bytes, so it can be hashed with poseidon
+pub(crate) fn memo_scalars(encrypted_memo: &[u8; ENCRYPTED_MEMO_SIZE]) -> [BlsScalar; 2] {
+    let mut scalars = [BlsScalar::zero(); 2];
+
+    encrypted_memo
+        .chunks(31)
+        .zip(scalars.iter_mut())
+        .for_each(|(c, s)| {
+            let mut bytes = [0x00u8; utils::BLS_SCALAR_SERIALIZED_SIZE];
+            bytes[..c.len()].copy_from_slice(c);
+
+            // 31 bytes are always lower than the modulus
+            *s = BlsScalar::from_bytes(&bytes).unwrap();
+        });
+
+    scalars
+}
+
+/// Candidates of `H(a · R)`, from the current to the legacy hash
+///
+/// The notes created before [`crypto::hash_to_jubjub_scalar`] have their `PKr` derived with
+/// [`crypto::hash_jubjub_projective_to_jubjub_scalar`]. They are still recognized and spendable;
+/// the wallets are expected to spend them to new notes, and the fallback will be removed once
+/// the legacy notes are no longer relevant.
+#[allow(deprecated)]
+pub(crate) fn shared_secret_hashes(aR: &JubJubExtended) -> [JubJubScalar; 2] {
+    [
+        crypto::hash_to_jubjub_scalar(aR),
+        crypto::hash_jubjub_projective_to_jubjub_scalar(aR),
+    ]
 }
 
 impl TryFrom<i32> for NoteType {
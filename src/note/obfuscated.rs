@@ -1,6 +1,6 @@
 use crate::{
     crypto, rpc, utils, BlsScalar, Error, JubJubAffine, JubJubExtended, JubJubScalar, Nonce, Note,
-    NoteGenerator, NoteType, PublicKey, ViewKey, NONCEBYTES,
+    NoteGenerator, NoteType, PublicKey, ViewKey, ENCRYPTED_MEMO_SIZE, MEMO_SIZE, NONCEBYTES,
 };
 
 use rand;
@@ -17,8 +17,19 @@ use unprolix::Constructor;
 pub const ENCRYPTED_VALUE_SIZE: usize = 24;
 /// Size of the encrypted blinding factor
 pub const ENCRYPTED_BLINDING_FACTOR_SIZE: usize = 48;
-
-/// A note that hides its value and blinding factor
+/// Size of the encrypted asset id
+pub const ENCRYPTED_ASSET_SIZE: usize = 24;
+/// Serialized size of an obfuscated note
+pub const OBFUSCATED_NOTE_SERIALIZED_SIZE: usize = 3 * utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE
+    + NONCEBYTES
+    + 8
+    + ENCRYPTED_VALUE_SIZE
+    + ENCRYPTED_BLINDING_FACTOR_SIZE
+    + ENCRYPTED_MEMO_SIZE
+    + ENCRYPTED_ASSET_SIZE
+    + 8;
+
+/// A note that hides its value, asset and blinding factor
 #[derive(Clone, Copy, Constructor)]
 pub struct ObfuscatedNote {
     value_commitment: JubJubExtended,
@@ -28,11 +39,14 @@ pub struct ObfuscatedNote {
     idx: u64,
     pub encrypted_value: [u8; ENCRYPTED_VALUE_SIZE],
     pub encrypted_blinding_factor: [u8; ENCRYPTED_BLINDING_FACTOR_SIZE],
+    pub encrypted_memo: [u8; ENCRYPTED_MEMO_SIZE],
+    pub encrypted_asset: [u8; ENCRYPTED_ASSET_SIZE],
+    unlock_height: u64,
 }
 
 impl fmt::Debug for ObfuscatedNote {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "ObfuscatedNote {{ nonce: {:?}, R: {:?}, pk_r: {:?}, idx: {:?}, value_commitment: {:?}, encrypted_value: {:?}, encrypted_blinding_factor: {:?} }}", self.nonce, self.R, self.pk_r, self.idx, self.value_commitment, &self.encrypted_value, &self.encrypted_blinding_factor[0..32])
+        write!(f, "ObfuscatedNote {{ nonce: {:?}, R: {:?}, pk_r: {:?}, idx: {:?}, value_commitment: {:?}, encrypted_value: {:?}, encrypted_blinding_factor: {:?}, encrypted_memo: {:?}, encrypted_asset: {:?}, unlock_height: {:?} }}", self.nonce, self.R, self.pk_r, self.idx, self.value_commitment, &self.encrypted_value, &self.encrypted_blinding_factor[0..32], &self.encrypted_memo[0..32], &self.encrypted_asset, self.unlock_height)
     }
 }
 
@@ -109,6 +123,30 @@ impl Read for ObfuscatedNote {
             .and_then(|mut c| Ok(c.write(&self.encrypted_blinding_factor)?))
             .map_err::<io::Error, _>(|e| e.into())?;
         n += ENCRYPTED_BLINDING_FACTOR_SIZE;
+        buf = &mut buf[ENCRYPTED_BLINDING_FACTOR_SIZE..];
+
+        buf.chunks_mut(ENCRYPTED_MEMO_SIZE)
+            .next()
+            .ok_or(Error::InvalidParameters)
+            .and_then(|mut c| Ok(c.write(&self.encrypted_memo)?))
+            .map_err::<io::Error, _>(|e| e.into())?;
+        n += ENCRYPTED_MEMO_SIZE;
+        buf = &mut buf[ENCRYPTED_MEMO_SIZE..];
+
+        buf.chunks_mut(ENCRYPTED_ASSET_SIZE)
+            .next()
+            .ok_or(Error::InvalidParameters)
+            .and_then(|mut c| Ok(c.write(&self.encrypted_asset)?))
+            .map_err::<io::Error, _>(|e| e.into())?;
+        n += ENCRYPTED_ASSET_SIZE;
+        buf = &mut buf[ENCRYPTED_ASSET_SIZE..];
+
+        buf.chunks_mut(8)
+            .next()
+            .ok_or(Error::InvalidParameters)
+            .and_then(|mut c| Ok(c.write(&self.unlock_height.to_le_bytes())?))
+            .map_err::<io::Error, _>(|e| e.into())?;
+        n += 8;
 
         Ok(n)
     }
@@ -122,7 +160,7 @@ impl Write for ObfuscatedNote {
             .chunks(utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE)
             .next()
             .ok_or(Error::InvalidParameters)
-            .and_then(utils::deserialize_compressed_jubjub)
+            .and_then(utils::deserialize_strict_jubjub)
             .map_err::<io::Error, _>(|e| e.into())?;
         n += utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE;
         buf = &buf[utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE..];
@@ -144,7 +182,7 @@ impl Write for ObfuscatedNote {
             .chunks(utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE)
             .next()
             .ok_or(Error::InvalidParameters)
-            .and_then(utils::deserialize_compressed_jubjub)
+            .and_then(utils::deserialize_strict_jubjub)
             .map_err::<io::Error, _>(|e| e.into())?;
         n += utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE;
         buf = &buf[utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE..];
@@ -153,7 +191,7 @@ impl Write for ObfuscatedNote {
             .chunks(utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE)
             .next()
             .ok_or(Error::InvalidParameters)
-            .and_then(utils::deserialize_compressed_jubjub)
+            .and_then(utils::deserialize_strict_jubjub)
             .map_err::<io::Error, _>(|e| e.into())?;
         n += utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE;
         buf = &buf[utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE..];
@@ -195,6 +233,45 @@ impl Write for ObfuscatedNote {
             })
             .map_err::<io::Error, _>(|e| e.into())?;
         n += ENCRYPTED_BLINDING_FACTOR_SIZE;
+        buf = &buf[ENCRYPTED_BLINDING_FACTOR_SIZE..];
+
+        let encrypted_memo = buf
+            .chunks(ENCRYPTED_MEMO_SIZE)
+            .next()
+            .ok_or(Error::InvalidParameters)
+            .and_then(|c| {
+                let mut v = [0x00u8; ENCRYPTED_MEMO_SIZE];
+                (&mut v[..]).write(c)?;
+                Ok(v)
+            })
+            .map_err::<io::Error, _>(|e| e.into())?;
+        n += ENCRYPTED_MEMO_SIZE;
+        buf = &buf[ENCRYPTED_MEMO_SIZE..];
+
+        let encrypted_asset = buf
+            .chunks(ENCRYPTED_ASSET_SIZE)
+            .next()
+            .ok_or(Error::InvalidParameters)
+            .and_then(|c| {
+                let mut v = [0x00u8; ENCRYPTED_ASSET_SIZE];
+                (&mut v[..]).write(c)?;
+                Ok(v)
+            })
+            .map_err::<io::Error, _>(|e| e.into())?;
+        n += ENCRYPTED_ASSET_SIZE;
+        buf = &buf[ENCRYPTED_ASSET_SIZE..];
+
+        let unlock_height = buf
+            .chunks(8)
+            .next()
+            .ok_or(Error::InvalidParameters)
+            .and_then(|c| {
+                let mut h = [0x00u8; 8];
+                (&mut h[..]).write(c)?;
+                Ok(u64::from_le_bytes(h))
+            })
+            .map_err::<io::Error, _>(|e| e.into())?;
+        n += 8;
 
         self.value_commitment = value_commitment;
         self.nonce = nonce;
@@ -203,6 +280,9 @@ impl Write for ObfuscatedNote {
         self.idx = idx;
         self.encrypted_value = encrypted_value;
         self.encrypted_blinding_factor = encrypted_blinding_factor;
+        self.encrypted_memo = encrypted_memo;
+        self.encrypted_asset = encrypted_asset;
+        self.unlock_height = unlock_height;
 
         Ok(n)
     }
@@ -213,12 +293,14 @@ impl Write for ObfuscatedNote {
 }
 
 impl NoteGenerator for ObfuscatedNote {
-    fn deterministic_output(
+    fn deterministic_output_with_asset(
         r: &JubJubScalar,
         nonce: Nonce,
         pk: &PublicKey,
+        asset: u64,
         value: u64,
         blinding_factor: JubJubScalar,
+        memo: &[u8; MEMO_SIZE],
     ) -> Self {
         let (R, pk_r) = Self::new_pk_r(r, pk);
         let value_commitment = JubJubScalar::from(value);
@@ -242,15 +324,18 @@ impl NoteGenerator for ObfuscatedNote {
         let second_bytes = JubJubAffine::from(second_point).to_bytes();
         let second_point = JubJubExtended::from(JubJubAffine::from_bytes(second_bytes).unwrap());
         let value_commitment =
-            (JubJubExtended::from(GENERATOR) * value_commitment) + (second_point * blinding_factor);
+            (crypto::asset_generator(asset) * value_commitment) + (second_point * blinding_factor);
         let value_commitment = JubJubExtended::from(JubJubAffine::from(value_commitment));
 
-        // Output notes have undefined idx
+        // Output notes have undefined idx, and are unlocked
         let idx = 0;
+        let unlock_height = 0;
 
         let encrypted_value = ObfuscatedNote::encrypt_value(&r, pk, &nonce, value);
         let encrypted_blinding_factor =
             ObfuscatedNote::encrypt_blinding_factor(&r, pk, &nonce, &blinding_factor);
+        let encrypted_memo = ObfuscatedNote::encrypt_memo(&r, pk, &nonce, memo);
+        let encrypted_asset = ObfuscatedNote::encrypt_asset(&r, pk, &nonce, asset);
 
         ObfuscatedNote::new(
             value_commitment,
@@ -260,6 +345,9 @@ impl NoteGenerator for ObfuscatedNote {
             idx,
             encrypted_value,
             encrypted_blinding_factor,
+            encrypted_memo,
+            encrypted_asset,
+            unlock_height,
         )
     }
 }
@@ -277,6 +365,14 @@ impl Note for ObfuscatedNote {
         self.idx = idx;
     }
 
+    fn unlock_height(&self) -> u64 {
+        self.unlock_height
+    }
+
+    fn set_unlock_height(&mut self, unlock_height: u64) {
+        self.unlock_height = unlock_height;
+    }
+
     fn nonce(&self) -> &Nonce {
         &self.nonce
     }
@@ -289,42 +385,78 @@ impl Note for ObfuscatedNote {
         &self.pk_r
     }
 
-    fn value(&self, vk: Option<&ViewKey>) -> u64 {
-        let vk = vk.copied().unwrap_or_default();
+    fn value(&self, vk: Option<&ViewKey>) -> Result<u64, Error> {
+        let vk = match vk {
+            Some(vk) => vk,
+            None => return Err(Error::DecryptionFailed),
+        };
 
-        let decrypt_value = crypto::decrypt(&self.R, &vk, &self.nonce, &self.encrypted_value[..]);
+        let decrypt_value = crypto::decrypt(&self.R, vk, &self.nonce, &self.encrypted_value[..])?;
 
         let mut v = [0x00u8; 8];
         let chunk = cmp::min(decrypt_value.len(), 8);
         (&mut v[0..chunk]).copy_from_slice(&decrypt_value.as_slice()[0..chunk]);
 
-        u64::from_le_bytes(v)
+        Ok(u64::from_le_bytes(v))
     }
 
     fn encrypted_value(&self) -> Option<&[u8; ENCRYPTED_VALUE_SIZE]> {
         Some(&self.encrypted_value)
     }
 
+    fn asset(&self, vk: Option<&ViewKey>) -> Result<u64, Error> {
+        let vk = match vk {
+            Some(vk) => vk,
+            None => return Err(Error::DecryptionFailed),
+        };
+
+        let nonce = self.nonce.increment_le().increment_le().increment_le();
+        let decrypt_asset = crypto::decrypt(&self.R, vk, &nonce, &self.encrypted_asset[..])?;
+
+        if decrypt_asset.len() != 8 {
+            return Err(Error::InvalidParameters);
+        }
+
+        let mut a = [0x00u8; 8];
+        a.copy_from_slice(decrypt_asset.as_slice());
+
+        Ok(u64::from_le_bytes(a))
+    }
+
+    fn encrypted_asset(&self) -> Option<&[u8; ENCRYPTED_ASSET_SIZE]> {
+        Some(&self.encrypted_asset)
+    }
+
     fn value_commitment(&self) -> &JubJubExtended {
         &self.value_commitment
     }
 
     fn blinding_factor(&self, vk: Option<&ViewKey>) -> Result<JubJubScalar, Error> {
-        let vk = vk.copied().unwrap_or_default();
+        let vk = match vk {
+            Some(vk) => vk,
+            None => return Err(Error::DecryptionFailed),
+        };
 
-        let blinding_factor = crypto::decrypt(
+        let mut blinding_factor = crypto::decrypt(
             &self.R,
-            &vk,
+            vk,
             &self.nonce.increment_le(),
             &self.encrypted_blinding_factor[..],
-        );
+        )?;
 
-        utils::deserialize_jubjub_scalar(blinding_factor.as_slice()).map_err(|e| e.into())
+        let result = utils::deserialize_jubjub_scalar(blinding_factor.as_slice());
+        utils::wipe_bytes(blinding_factor.as_mut_slice());
+
+        result
     }
 
     fn encrypted_blinding_factor(&self) -> &[u8; ENCRYPTED_BLINDING_FACTOR_SIZE] {
         &self.encrypted_blinding_factor
     }
+
+    fn encrypted_memo(&self) -> &[u8; ENCRYPTED_MEMO_SIZE] {
+        &self.encrypted_memo
+    }
 }
 
 impl From<ObfuscatedNote> for rpc::Note {
@@ -341,6 +473,11 @@ impl From<ObfuscatedNote> for rpc::Note {
         let blinding_factor = Some(rpc::note::BlindingFactor::EncryptedBlindingFactor(
             note.encrypted_value.to_vec(),
         ));
+        let encrypted_memo = note.encrypted_memo.to_vec();
+        let asset = Some(rpc::note::Asset::EncryptedAsset(
+            note.encrypted_asset.to_vec(),
+        ));
+        let unlock_height = note.unlock_height;
 
         rpc::Note {
             note_type,
@@ -351,6 +488,9 @@ impl From<ObfuscatedNote> for rpc::Note {
             value_commitment,
             blinding_factor,
             value,
+            encrypted_memo,
+            asset,
+            unlock_height,
         }
     }
 }
@@ -387,6 +527,21 @@ impl TryFrom<rpc::Note> for ObfuscatedNote {
             }?;
         let encrypted_blinding_factor = utils::safe_48_chunk(encrypted_blinding_factor.as_slice());
 
+        if note.encrypted_memo.len() != ENCRYPTED_MEMO_SIZE {
+            return Err(Error::InvalidParameters);
+        }
+        let encrypted_memo = utils::safe_48_chunk(note.encrypted_memo.as_slice());
+
+        let encrypted_asset = match note.asset.ok_or(Error::InvalidParameters)? {
+            rpc::note::Asset::TransparentAsset(_) => Err(Error::InvalidParameters),
+            rpc::note::Asset::EncryptedAsset(a) => Ok(a),
+        }?;
+        if encrypted_asset.len() != ENCRYPTED_ASSET_SIZE {
+            return Err(Error::InvalidParameters);
+        }
+        let encrypted_asset = utils::safe_24_chunk(encrypted_asset.as_slice());
+        let unlock_height = note.unlock_height;
+
         Ok(ObfuscatedNote::new(
             value_commitment,
             nonce,
@@ -395,6 +550,9 @@ impl TryFrom<rpc::Note> for ObfuscatedNote {
             idx,
             encrypted_value,
             encrypted_blinding_factor,
+            encrypted_memo,
+            encrypted_asset,
+            unlock_height,
         ))
     }
 }
@@ -427,6 +585,21 @@ impl TryFrom<rpc::DecryptedNote> for ObfuscatedNote {
             }?;
         let encrypted_blinding_factor = utils::safe_48_chunk(encrypted_blinding_factor.as_slice());
 
+        if note.encrypted_memo.len() != ENCRYPTED_MEMO_SIZE {
+            return Err(Error::InvalidParameters);
+        }
+        let encrypted_memo = utils::safe_48_chunk(note.encrypted_memo.as_slice());
+
+        let encrypted_asset = match note.raw_asset.ok_or(Error::InvalidParameters)? {
+            rpc::decrypted_note::RawAsset::EncryptedAsset(a) => Ok(a),
+            _ => Err(Error::InvalidParameters),
+        }?;
+        if encrypted_asset.len() != ENCRYPTED_ASSET_SIZE {
+            return Err(Error::InvalidParameters);
+        }
+        let encrypted_asset = utils::safe_24_chunk(encrypted_asset.as_slice());
+        let unlock_height = note.unlock_height;
+
         Ok(ObfuscatedNote::new(
             value_commitment,
             nonce,
@@ -435,6 +608,9 @@ impl TryFrom<rpc::DecryptedNote> for ObfuscatedNote {
             idx,
             encrypted_value,
             encrypted_blinding_factor,
+            encrypted_memo,
+            encrypted_asset,
+            unlock_height,
         ))
     }
 }
@@ -450,6 +626,9 @@ impl<H: ByteHash> Content<H> for ObfuscatedNote {
 
         sink.write_all(&self.encrypted_value[..])?;
         sink.write_all(&self.encrypted_blinding_factor[..])?;
+        sink.write_all(&self.encrypted_memo[..])?;
+        sink.write_all(&self.encrypted_asset[..])?;
+        self.unlock_height.persist(sink)?;
 
         Ok(())
     }
@@ -468,6 +647,14 @@ impl<H: ByteHash> Content<H> for ObfuscatedNote {
         let mut encrypted_blinding_factor = [0x00u8; ENCRYPTED_BLINDING_FACTOR_SIZE];
         source.read_exact(&mut encrypted_blinding_factor)?;
 
+        let mut encrypted_memo = [0x00u8; ENCRYPTED_MEMO_SIZE];
+        source.read_exact(&mut encrypted_memo)?;
+
+        let mut encrypted_asset = [0x00u8; ENCRYPTED_ASSET_SIZE];
+        source.read_exact(&mut encrypted_asset)?;
+
+        let unlock_height = u64::restore(source)?;
+
         Ok(ObfuscatedNote::new(
             value_commitment,
             nonce,
@@ -476,6 +663,9 @@ impl<H: ByteHash> Content<H> for ObfuscatedNote {
             idx,
             encrypted_value,
             encrypted_blinding_factor,
+            encrypted_memo,
+            encrypted_asset,
+            unlock_height,
         ))
     }
 }
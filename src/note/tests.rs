@@ -1,6 +1,7 @@
 use crate::{
-    rpc, JubJubExtended, Note, NoteGenerator, NoteType, NoteVariant, ObfuscatedNote, PublicKey,
-    SecretKey, TransparentNote,
+    crypto, rpc, utils, BlsScalar, Error, JubJubAffine, JubJubExtended, JubJubScalar, Note,
+    NoteGenerator, NoteType, NoteVariant, ObfuscatedNote, PublicKey, SecretKey, SubaddressTable,
+    TransparentNote, MEMO_SIZE, NATIVE_ASSET,
 };
 
 use jubjub::GENERATOR;
@@ -35,7 +36,7 @@ fn transparent_note() {
 
     let note = deser_note;
     assert_eq!(note.note(), NoteType::Transparent);
-    assert_eq!(value, note.value(None));
+    assert_eq!(value, note.value(None).unwrap());
 }
 
 #[test]
@@ -70,7 +71,7 @@ fn obfuscated_note() {
     let deserialized_note = ObfuscatedNote::try_from(rpc_decrypted_note).unwrap();
     assert_eq!(deserialized_note, note);
 
-    assert_eq!(value, note.value(Some(&vk)));
+    assert_eq!(value, note.value(Some(&vk)).unwrap());
 }
 
 #[test]
@@ -90,6 +91,15 @@ fn note_keys_consistency() {
     assert!(!note.is_owned_by(&wrong_vk));
     assert!(note.is_owned_by(&vk));
 
+    assert_eq!(value, note.value(Some(&vk)).unwrap());
+    assert!(note.blinding_factor(Some(&vk)).is_ok());
+    match note.value(Some(&wrong_vk)) {
+        Err(Error::DecryptionFailed) => (),
+        _ => panic!("The value must not be decrypted with the wrong view key"),
+    }
+    assert!(note.blinding_factor(Some(&wrong_vk)).is_err());
+    assert!(note.rpc_decrypted_note(&wrong_vk).is_err());
+
     let sk_r = note.sk_r(&sk);
     let wrong_sk_r = note.sk_r(&wrong_sk);
 
@@ -135,3 +145,366 @@ fn content_implementations() {
     fuzz_content::<u64, Blake2b>();
     fuzz_content_iterations::<NoteVariant, Blake2b>(64);
 }
+
+#[test]
+fn note_subaddresses() {
+    let sk = SecretKey::default();
+    let vk = sk.view_key();
+    let table = SubaddressTable::new(vk.clone(), 10);
+
+    assert_eq!(sk.public_key(), vk.subaddress(0));
+    assert_eq!(sk, sk.subaddress(0));
+    assert_ne!(vk.subaddress(1), vk.subaddress(2));
+    assert_eq!(vk.subaddress(1).A(), vk.subaddress(2).A());
+
+    for index in [0, 3, 9].iter() {
+        let pk = vk.subaddress(*index);
+        let sk_i = sk.subaddress(*index);
+        assert_eq!(pk, sk_i.public_key());
+
+        let note: NoteVariant = ObfuscatedNote::output(&pk, 25).0.into();
+        assert_eq!(Some(*index), table.index_of(&note));
+        assert_eq!(Some(*index), note.subaddress_index(&table));
+
+        // The notes are scanned with the view key, and spent with the secret of the subaddress
+        assert_eq!(25, note.value(Some(&vk)).unwrap());
+        assert!(note.is_owned_by(&vk.subaddress_view_key(*index)));
+        assert_eq!(
+            note.pk_r(),
+            &JubJubExtended::from(GENERATOR).mul(&note.sk_r(&sk_i))
+        );
+    }
+
+    let note = TransparentNote::output(&vk.subaddress(10), 25).0;
+    assert_eq!(None, table.index_of(&note));
+
+    let note = TransparentNote::output(&SecretKey::default().public_key(), 25).0;
+    assert_eq!(None, table.index_of(&note));
+}
+
+/// `H(a · R)` of the notes created before the wide reduction of the stealth address hash: the
+/// untagged sponge hash of the projective coordinates, reinterpreted bit by bit as a jubjub
+/// scalar. Rebuilt here, independently of [`crypto`], so a change of the crate hashes can't
+/// silently break the recognition of these notes.
+fn legacy_stealth_hash(p: &JubJubExtended) -> JubJubScalar {
+    let p = JubJubExtended::from(JubJubAffine::from(p));
+    let s = crypto::sponge_hash(&[p.get_x(), p.get_y(), p.get_z(), p.get_t1(), p.get_t2()]);
+
+    let mut acc = JubJubScalar::one();
+    let mut result = JubJubScalar::zero();
+    for bit in utils::bls_scalar_to_bits(&s).iter() {
+        acc *= &JubJubScalar::from(2u64);
+        if bit == &1u8 {
+            result += &acc;
+        }
+    }
+
+    result
+}
+
+#[test]
+fn note_legacy_stealth_hash() {
+    let sk = SecretKey::default();
+    let pk = sk.public_key();
+    let vk = sk.view_key();
+    let value = 25;
+
+    let (r, R, pk_r) = ObfuscatedNote::generate_pk_r(&pk);
+    let rA = pk.A().mul(&r);
+    assert_eq!(
+        pk_r,
+        JubJubExtended::from(GENERATOR).mul(&crypto::hash_to_jubjub_scalar(&rA)) + pk.B()
+    );
+
+    // Note created before the wide reduction of the stealth address hash
+    let legacy_pk_r = JubJubExtended::from(GENERATOR).mul(&legacy_stealth_hash(&rA)) + pk.B();
+    let legacy_pk_r = JubJubExtended::from(JubJubAffine::from(legacy_pk_r));
+    assert_ne!(pk_r, legacy_pk_r);
+
+    let blinding_factor = utils::gen_random_scalar();
+    let note =
+        ObfuscatedNote::deterministic_output(&r, utils::gen_nonce(), &pk, value, blinding_factor);
+    let note = ObfuscatedNote::new(
+        *note.value_commitment(),
+        *note.nonce(),
+        R,
+        legacy_pk_r,
+        note.idx(),
+        note.encrypted_value,
+        note.encrypted_blinding_factor,
+        note.encrypted_memo,
+        note.encrypted_asset,
+        note.unlock_height(),
+    );
+
+    assert!(note.is_owned_by(&vk));
+    assert!(!note.is_owned_by(&SecretKey::default().view_key()));
+    assert_eq!(value, note.value(Some(&vk)).unwrap());
+    assert_eq!(
+        note.pk_r(),
+        &JubJubExtended::from(GENERATOR).mul(&note.sk_r(&sk))
+    );
+
+    let table = SubaddressTable::new(vk, 1);
+    assert_eq!(Some(0), note.subaddress_index(&table));
+}
+
+#[test]
+fn note_memo() {
+    let sk = SecretKey::default();
+    let pk = sk.public_key();
+    let vk = sk.view_key();
+    let wrong_vk = SecretKey::default().view_key();
+    let memo = [0xfau8; MEMO_SIZE];
+
+    let (note, _) = ObfuscatedNote::output_with_memo(&pk, 25, &memo);
+    assert_eq!(Some(memo), note.memo(&vk).unwrap());
+    assert!(matches!(note.memo(&wrong_vk), Err(Error::DecryptionFailed)));
+
+    let (plain, _) = ObfuscatedNote::output(&pk, 25);
+    assert_eq!(None, plain.memo(&vk).unwrap());
+    assert_ne!(note.hash(), plain.hash());
+
+    let rpc_decrypted_note = note.clone().rpc_decrypted_note(&vk).unwrap();
+    assert_eq!(memo.to_vec(), rpc_decrypted_note.memo);
+    assert_eq!(
+        note,
+        ObfuscatedNote::try_from(rpc_decrypted_note.clone()).unwrap()
+    );
+
+    let mut truncated = rpc_decrypted_note;
+    truncated.encrypted_memo.pop();
+    assert!(matches!(
+        ObfuscatedNote::try_from(truncated),
+        Err(Error::InvalidParameters)
+    ));
+
+    let mut rpc_note: rpc::Note = note.into();
+    rpc_note.encrypted_memo.push(0x00);
+    assert!(matches!(
+        ObfuscatedNote::try_from(rpc_note),
+        Err(Error::InvalidParameters)
+    ));
+
+    // The note is still decrypted if its memo is not
+    let mut corrupted = note;
+    corrupted.encrypted_memo[0] ^= 0x01;
+    let rpc_decrypted_note = corrupted.rpc_decrypted_note(&vk).unwrap();
+    assert!(rpc_decrypted_note.memo.is_empty());
+    assert_eq!(25, rpc_decrypted_note.value);
+
+    let mut bytes = vec![0x00u8; 2048];
+    let mut variant: NoteVariant = note.into();
+    variant.read(bytes.as_mut_slice()).unwrap();
+
+    let mut deser_note: NoteVariant = ObfuscatedNote::default().into();
+    deser_note.write(bytes.as_slice()).unwrap();
+    assert_eq!(Some(memo), deser_note.memo(&vk).unwrap());
+
+    let (note, _) = TransparentNote::output_with_memo(&pk, 25, &memo);
+    assert_eq!(Some(memo), note.memo(&vk).unwrap());
+}
+
+#[test]
+fn note_assets() {
+    let sk = SecretKey::default();
+    let pk = sk.public_key();
+    let vk = sk.view_key();
+    let memo = [0x00u8; MEMO_SIZE];
+    let asset = 7;
+
+    assert_eq!(
+        JubJubExtended::from(GENERATOR),
+        crypto::asset_generator(NATIVE_ASSET)
+    );
+    assert_ne!(
+        crypto::asset_generator(NATIVE_ASSET),
+        crypto::asset_generator(asset)
+    );
+
+    let (note, _) = ObfuscatedNote::output_with_asset(&pk, asset, 25, &memo);
+    assert_eq!(asset, note.asset(Some(&vk)).unwrap());
+    assert!(matches!(
+        note.asset(Some(&SecretKey::default().view_key())),
+        Err(Error::DecryptionFailed)
+    ));
+    assert!(matches!(note.asset(None), Err(Error::DecryptionFailed)));
+
+    let mut bytes = vec![0x00u8; 2048];
+    let mut variant: NoteVariant = note.into();
+    variant.read(bytes.as_mut_slice()).unwrap();
+
+    let mut deser_note: NoteVariant = ObfuscatedNote::default().into();
+    deser_note.write(bytes.as_slice()).unwrap();
+    assert_eq!(asset, deser_note.asset(Some(&vk)).unwrap());
+
+    let rpc_note = rpc::Note::from(note);
+    assert_eq!(note, ObfuscatedNote::try_from(rpc_note.clone()).unwrap());
+
+    let mut truncated = rpc_note;
+    if let Some(rpc::note::Asset::EncryptedAsset(a)) = truncated.asset.as_mut() {
+        a.pop();
+    }
+    assert!(matches!(
+        ObfuscatedNote::try_from(truncated),
+        Err(Error::InvalidParameters)
+    ));
+
+    let mut rpc_decrypted_note = note.rpc_decrypted_note(&vk).unwrap();
+    if let Some(rpc::decrypted_note::RawAsset::EncryptedAsset(a)) =
+        rpc_decrypted_note.raw_asset.as_mut()
+    {
+        a.push(0x00);
+    }
+    assert!(matches!(
+        ObfuscatedNote::try_from(rpc_decrypted_note),
+        Err(Error::InvalidParameters)
+    ));
+
+    // The same value and blinding factor are committed differently for each asset
+    let r = utils::gen_random_scalar();
+    let nonce = utils::gen_nonce();
+    let blinding_factor = utils::gen_random_scalar();
+    let native = TransparentNote::deterministic_output(&r, nonce, &pk, 25, blinding_factor);
+    let token = TransparentNote::deterministic_output_with_asset(
+        &r,
+        nonce,
+        &pk,
+        asset,
+        25,
+        blinding_factor,
+        &memo,
+    );
+    assert_eq!(NATIVE_ASSET, native.asset(None).unwrap());
+    assert_eq!(asset, token.asset(None).unwrap());
+    assert_ne!(native.value_commitment(), token.value_commitment());
+
+    let rpc_decrypted_note = token.rpc_decrypted_note(&vk).unwrap();
+    assert_eq!(asset, rpc_decrypted_note.asset);
+    assert_eq!(
+        token,
+        TransparentNote::try_from(rpc_decrypted_note).unwrap()
+    );
+}
+
+#[test]
+fn note_unlock_height() {
+    let sk = SecretKey::default();
+    let pk = sk.public_key();
+    let vk = sk.view_key();
+
+    let (mut note, _) = ObfuscatedNote::output(&pk, 25);
+    assert_eq!(0, note.unlock_height());
+    assert!(!note.is_locked(0));
+
+    let hash = note.hash();
+    note.set_unlock_height(100);
+    assert_ne!(hash, note.hash());
+    assert!(note.is_locked(99));
+    assert!(!note.is_locked(100));
+
+    let mut bytes = vec![0x00u8; 2048];
+    let mut variant: NoteVariant = note.into();
+    variant.read(bytes.as_mut_slice()).unwrap();
+
+    let mut deser_note: NoteVariant = ObfuscatedNote::default().into();
+    deser_note.write(bytes.as_slice()).unwrap();
+    assert_eq!(100, deser_note.unlock_height());
+    assert_eq!(note.hash(), deser_note.hash());
+
+    let rpc_note = rpc::Note::from(note);
+    assert_eq!(100, rpc_note.unlock_height);
+    assert_eq!(note, ObfuscatedNote::try_from(rpc_note).unwrap());
+
+    let rpc_decrypted_note = note.rpc_decrypted_note(&vk).unwrap();
+    assert_eq!(100, rpc_decrypted_note.unlock_height);
+    assert_eq!(note, ObfuscatedNote::try_from(rpc_decrypted_note).unwrap());
+}
+
+#[test]
+fn note_strict_decoding() {
+    let pk = SecretKey::default().public_key();
+
+    let transparent: NoteVariant = TransparentNote::output(&pk, 25).0.into();
+    let obfuscated: NoteVariant = ObfuscatedNote::output(&pk, 25).0.into();
+
+    for note in [transparent, obfuscated].iter() {
+        let bytes = note.to_bytes().unwrap();
+        let decoded = NoteVariant::from_bytes(bytes.as_slice()).unwrap();
+        assert_eq!(note, &decoded);
+        assert_eq!(bytes, decoded.to_bytes().unwrap());
+
+        let mut trailing = bytes.clone();
+        trailing.push(0x00);
+        assert!(matches!(
+            NoteVariant::from_bytes(trailing.as_slice()),
+            Err(Error::NonCanonicalEncoding)
+        ));
+        assert!(matches!(
+            NoteVariant::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(Error::InvalidParameters)
+        ));
+
+        // The type byte is followed by the value commitment and the nonce
+        for offset in [57, 89].iter() {
+            let with_point = |point: [u8; 32]| {
+                let mut b = bytes.clone();
+                b[*offset..*offset + 32].copy_from_slice(&point);
+                NoteVariant::from_bytes(b.as_slice())
+            };
+
+            let identity = JubJubAffine::identity().to_bytes();
+            assert!(matches!(with_point(identity), Err(Error::IdentityPoint)));
+
+            // The identity, with the sign bit of `u = 0` set
+            let mut signed_identity = identity;
+            signed_identity[31] |= 0x80;
+            assert!(matches!(
+                with_point(signed_identity),
+                Err(Error::NonCanonicalEncoding)
+            ));
+
+            // (0, -1) is of order 2
+            let small_order = (-BlsScalar::one()).to_bytes();
+            assert!(matches!(
+                with_point(small_order),
+                Err(Error::SmallOrderPoint)
+            ));
+
+            // `v` out of the field
+            let mut overflow = [0xffu8; 32];
+            overflow[31] = 0x7f;
+            assert!(matches!(
+                with_point(overflow),
+                Err(Error::NonCanonicalEncoding)
+            ));
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "serde-support")]
+fn note_serde() {
+    let pk = SecretKey::default().public_key();
+
+    let note: NoteVariant = TransparentNote::output(&pk, 25).0.into();
+    let json = serde_json::to_value(&note).unwrap();
+    assert!(json.get("transparent").is_some());
+    assert_eq!(note, serde_json::from_value::<NoteVariant>(json).unwrap());
+
+    let note: NoteVariant = ObfuscatedNote::output(&pk, 25).0.into();
+    let mut json = serde_json::to_value(&note).unwrap();
+    assert_eq!(
+        note,
+        serde_json::from_value::<NoteVariant>(json.clone()).unwrap()
+    );
+
+    // The encrypted fields have a fixed size
+    let encrypted_value = json["obfuscated"]["encrypted_value"].as_str().unwrap();
+    json["obfuscated"]["encrypted_value"] = encrypted_value[2..].into();
+    assert!(serde_json::from_value::<NoteVariant>(json.clone()).is_err());
+
+    json["obfuscated"]["encrypted_value"] = encrypted_value.into();
+    json["obfuscated"]["unknown"] = 0.into();
+    assert!(serde_json::from_value::<NoteVariant>(json).is_err());
+}
@@ -1,6 +1,6 @@
 use crate::{
-    rpc, utils, BlsScalar, Error, JubJubAffine, JubJubExtended, JubJubScalar, Nonce, Note,
-    NoteGenerator, NoteType, PublicKey, ViewKey, NONCEBYTES,
+    crypto, rpc, utils, BlsScalar, Error, JubJubAffine, JubJubExtended, JubJubScalar, Nonce, Note,
+    NoteGenerator, NoteType, PublicKey, ViewKey, ENCRYPTED_MEMO_SIZE, MEMO_SIZE, NONCEBYTES,
 };
 
 use std::convert::{TryFrom, TryInto};
@@ -11,6 +11,16 @@ use jubjub::GENERATOR;
 use kelvin::{ByteHash, Content, Sink, Source};
 use unprolix::Constructor;
 
+/// Serialized size of a transparent note
+pub const TRANSPARENT_NOTE_SERIALIZED_SIZE: usize = 3 * utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE
+    + NONCEBYTES
+    + 8
+    + 8
+    + utils::JUBJUB_SCALAR_SERIALIZED_SIZE
+    + ENCRYPTED_MEMO_SIZE
+    + 8
+    + 8;
+
 /// A note that does not encrypt its value
 #[derive(Debug, Clone, Copy, Constructor)]
 pub struct TransparentNote {
@@ -21,6 +31,9 @@ pub struct TransparentNote {
     idx: u64,
     pub value: u64,
     pub blinding_factor: JubJubScalar,
+    pub encrypted_memo: [u8; ENCRYPTED_MEMO_SIZE],
+    pub asset: u64,
+    unlock_height: u64,
 }
 
 impl PartialEq for TransparentNote {
@@ -96,6 +109,30 @@ impl Read for TransparentNote {
             .and_then(|c| Ok(c.copy_from_slice(&self.blinding_factor.to_bytes()[..])))
             .map_err::<io::Error, _>(|e| e.into())?;
         n += utils::BLS_SCALAR_SERIALIZED_SIZE;
+        buf = &mut buf[utils::BLS_SCALAR_SERIALIZED_SIZE..];
+
+        buf.chunks_mut(ENCRYPTED_MEMO_SIZE)
+            .next()
+            .ok_or(Error::InvalidParameters)
+            .and_then(|mut c| Ok(c.write(&self.encrypted_memo)?))
+            .map_err::<io::Error, _>(|e| e.into())?;
+        n += ENCRYPTED_MEMO_SIZE;
+        buf = &mut buf[ENCRYPTED_MEMO_SIZE..];
+
+        buf.chunks_mut(8)
+            .next()
+            .ok_or(Error::InvalidParameters)
+            .and_then(|mut c| Ok(c.write(&self.asset.to_le_bytes())?))
+            .map_err::<io::Error, _>(|e| e.into())?;
+        n += 8;
+        buf = &mut buf[8..];
+
+        buf.chunks_mut(8)
+            .next()
+            .ok_or(Error::InvalidParameters)
+            .and_then(|mut c| Ok(c.write(&self.unlock_height.to_le_bytes())?))
+            .map_err::<io::Error, _>(|e| e.into())?;
+        n += 8;
 
         Ok(n)
     }
@@ -109,7 +146,7 @@ impl Write for TransparentNote {
             .chunks(utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE)
             .next()
             .ok_or(Error::InvalidParameters)
-            .and_then(utils::deserialize_compressed_jubjub)
+            .and_then(utils::deserialize_strict_jubjub)
             .map_err::<io::Error, _>(|e| e.into())?;
         n += utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE;
         buf = &buf[utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE..];
@@ -131,7 +168,7 @@ impl Write for TransparentNote {
             .chunks(utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE)
             .next()
             .ok_or(Error::InvalidParameters)
-            .and_then(utils::deserialize_compressed_jubjub)
+            .and_then(utils::deserialize_strict_jubjub)
             .map_err::<io::Error, _>(|e| e.into())?;
         n += utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE;
         buf = &buf[utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE..];
@@ -140,7 +177,7 @@ impl Write for TransparentNote {
             .chunks(utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE)
             .next()
             .ok_or(Error::InvalidParameters)
-            .and_then(utils::deserialize_compressed_jubjub)
+            .and_then(utils::deserialize_strict_jubjub)
             .map_err::<io::Error, _>(|e| e.into())?;
         n += utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE;
         buf = &buf[utils::COMPRESSED_JUBJUB_SERIALIZED_SIZE..];
@@ -178,6 +215,45 @@ impl Write for TransparentNote {
             .and_then(utils::deserialize_jubjub_scalar)
             .map_err::<io::Error, _>(|e| e.into())?;
         n += utils::JUBJUB_SCALAR_SERIALIZED_SIZE;
+        buf = &buf[utils::JUBJUB_SCALAR_SERIALIZED_SIZE..];
+
+        let encrypted_memo = buf
+            .chunks(ENCRYPTED_MEMO_SIZE)
+            .next()
+            .ok_or(Error::InvalidParameters)
+            .and_then(|c| {
+                let mut v = [0x00u8; ENCRYPTED_MEMO_SIZE];
+                (&mut v[..]).write(c)?;
+                Ok(v)
+            })
+            .map_err::<io::Error, _>(|e| e.into())?;
+        n += ENCRYPTED_MEMO_SIZE;
+        buf = &buf[ENCRYPTED_MEMO_SIZE..];
+
+        let asset = buf
+            .chunks(8)
+            .next()
+            .ok_or(Error::InvalidParameters)
+            .and_then(|c| {
+                let mut a = [0x00u8; 8];
+                (&mut a[..]).write(c)?;
+                Ok(u64::from_le_bytes(a))
+            })
+            .map_err::<io::Error, _>(|e| e.into())?;
+        n += 8;
+        buf = &buf[8..];
+
+        let unlock_height = buf
+            .chunks(8)
+            .next()
+            .ok_or(Error::InvalidParameters)
+            .and_then(|c| {
+                let mut h = [0x00u8; 8];
+                (&mut h[..]).write(c)?;
+                Ok(u64::from_le_bytes(h))
+            })
+            .map_err::<io::Error, _>(|e| e.into())?;
+        n += 8;
 
         self.value_commitment = value_commitment;
         self.nonce = nonce;
@@ -186,6 +262,9 @@ impl Write for TransparentNote {
         self.idx = idx;
         self.value = value;
         self.blinding_factor = blinding_factor;
+        self.encrypted_memo = encrypted_memo;
+        self.asset = asset;
+        self.unlock_height = unlock_height;
 
         Ok(n)
     }
@@ -196,12 +275,14 @@ impl Write for TransparentNote {
 }
 
 impl NoteGenerator for TransparentNote {
-    fn deterministic_output(
+    fn deterministic_output_with_asset(
         r: &JubJubScalar,
         nonce: Nonce,
         pk: &PublicKey,
+        asset: u64,
         value: u64,
         blinding_factor: JubJubScalar,
+        memo: &[u8; MEMO_SIZE],
     ) -> Self {
         let (R, pk_r) = Self::new_pk_r(r, pk);
         let value_commitment = JubJubScalar::from(value);
@@ -225,11 +306,14 @@ impl NoteGenerator for TransparentNote {
         let second_bytes = JubJubAffine::from(second_point).to_bytes();
         let second_point = JubJubExtended::from(JubJubAffine::from_bytes(second_bytes).unwrap());
         let value_commitment =
-            (JubJubExtended::from(GENERATOR) * value_commitment) + (second_point * blinding_factor);
+            (crypto::asset_generator(asset) * value_commitment) + (second_point * blinding_factor);
         let value_commitment = JubJubExtended::from(JubJubAffine::from(value_commitment));
 
-        // Output notes have undefined idx
+        // Output notes have undefined idx, and are unlocked
         let idx = 0;
+        let unlock_height = 0;
+
+        let encrypted_memo = TransparentNote::encrypt_memo(&r, pk, &nonce, memo);
 
         TransparentNote::new(
             value_commitment,
@@ -239,6 +323,9 @@ impl NoteGenerator for TransparentNote {
             idx,
             value,
             blinding_factor,
+            encrypted_memo,
+            asset,
+            unlock_height,
         )
     }
 }
@@ -256,6 +343,14 @@ impl Note for TransparentNote {
         self.idx = idx;
     }
 
+    fn unlock_height(&self) -> u64 {
+        self.unlock_height
+    }
+
+    fn set_unlock_height(&mut self, unlock_height: u64) {
+        self.unlock_height = unlock_height;
+    }
+
     fn nonce(&self) -> &Nonce {
         &self.nonce
     }
@@ -268,14 +363,22 @@ impl Note for TransparentNote {
         &self.pk_r
     }
 
-    fn value(&self, _vk: Option<&ViewKey>) -> u64 {
-        self.value
+    fn value(&self, _vk: Option<&ViewKey>) -> Result<u64, Error> {
+        Ok(self.value)
     }
 
     fn encrypted_value(&self) -> Option<&[u8; 24]> {
         None
     }
 
+    fn asset(&self, _vk: Option<&ViewKey>) -> Result<u64, Error> {
+        Ok(self.asset)
+    }
+
+    fn encrypted_asset(&self) -> Option<&[u8; 24]> {
+        None
+    }
+
     fn value_commitment(&self) -> &JubJubExtended {
         &self.value_commitment
     }
@@ -287,6 +390,10 @@ impl Note for TransparentNote {
     fn encrypted_blinding_factor(&self) -> &[u8; 48] {
         &[0x00u8; 48]
     }
+
+    fn encrypted_memo(&self) -> &[u8; ENCRYPTED_MEMO_SIZE] {
+        &self.encrypted_memo
+    }
 }
 
 impl From<TransparentNote> for rpc::Note {
@@ -301,6 +408,9 @@ impl From<TransparentNote> for rpc::Note {
             note.blinding_factor.into(),
         ));
         let value = Some(rpc::note::Value::TransparentValue(note.value));
+        let encrypted_memo = note.encrypted_memo.to_vec();
+        let asset = Some(rpc::note::Asset::TransparentAsset(note.asset));
+        let unlock_height = note.unlock_height;
 
         rpc::Note {
             note_type,
@@ -311,6 +421,9 @@ impl From<TransparentNote> for rpc::Note {
             value_commitment,
             blinding_factor,
             value,
+            encrypted_memo,
+            asset,
+            unlock_height,
         }
     }
 }
@@ -343,6 +456,17 @@ impl TryFrom<rpc::Note> for TransparentNote {
             rpc::note::Value::EncryptedValue(_) => Err(Error::InvalidParameters),
         }?;
 
+        let asset = match note.asset.ok_or(Error::InvalidParameters)? {
+            rpc::note::Asset::TransparentAsset(a) => Ok(a),
+            rpc::note::Asset::EncryptedAsset(_) => Err(Error::InvalidParameters),
+        }?;
+        let unlock_height = note.unlock_height;
+
+        if note.encrypted_memo.len() != ENCRYPTED_MEMO_SIZE {
+            return Err(Error::InvalidParameters);
+        }
+        let encrypted_memo = utils::safe_48_chunk(note.encrypted_memo.as_slice());
+
         Ok(Self::new(
             value_commitment,
             nonce,
@@ -351,6 +475,9 @@ impl TryFrom<rpc::Note> for TransparentNote {
             idx,
             value,
             blinding_factor,
+            encrypted_memo,
+            asset,
+            unlock_height,
         ))
     }
 }
@@ -372,6 +499,12 @@ impl TryFrom<rpc::DecryptedNote> for TransparentNote {
             .blinding_factor
             .ok_or(Error::InvalidParameters)?
             .try_into()?;
+        let asset = note.asset;
+        let unlock_height = note.unlock_height;
+        if note.encrypted_memo.len() != ENCRYPTED_MEMO_SIZE {
+            return Err(Error::InvalidParameters);
+        }
+        let encrypted_memo = utils::safe_48_chunk(note.encrypted_memo.as_slice());
 
         Ok(Self::new(
             value_commitment,
@@ -381,6 +514,9 @@ impl TryFrom<rpc::DecryptedNote> for TransparentNote {
             idx,
             value,
             blinding_factor,
+            encrypted_memo,
+            asset,
+            unlock_height,
         ))
     }
 }
@@ -396,6 +532,9 @@ impl<H: ByteHash> Content<H> for TransparentNote {
         self.value.persist(sink)?;
 
         sink.write_all(&self.blinding_factor.to_bytes())?;
+        sink.write_all(&self.encrypted_memo[..])?;
+        self.asset.persist(sink)?;
+        self.unlock_height.persist(sink)?;
         Ok(())
     }
 
@@ -410,6 +549,12 @@ impl<H: ByteHash> Content<H> for TransparentNote {
 
         let blinding_factor = utils::kelvin_source_to_jubjub_scalar(source)?;
 
+        let mut encrypted_memo = [0x00u8; ENCRYPTED_MEMO_SIZE];
+        source.read_exact(&mut encrypted_memo)?;
+
+        let asset = u64::restore(source)?;
+        let unlock_height = u64::restore(source)?;
+
         Ok(TransparentNote::new(
             value_commitment,
             nonce,
@@ -418,6 +563,9 @@ impl<H: ByteHash> Content<H> for TransparentNote {
             idx,
             value,
             blinding_factor,
+            encrypted_memo,
+            asset,
+            unlock_height,
         ))
     }
 }
@@ -1,11 +1,15 @@
 use crate::{
     crypto, rpc, BlsScalar, Error, JubJubAffine, JubJubExtended, JubJubScalar, Nonce, Note,
     NoteGenerator, NoteType, ObfuscatedNote, SecretKey, TransactionInput, TransparentNote, ViewKey,
+    ENCRYPTED_MEMO_SIZE,
 };
 
 use std::convert::{TryFrom, TryInto};
 use std::io::{self, Read, Write};
 
+use super::obfuscated::OBFUSCATED_NOTE_SERIALIZED_SIZE;
+use super::transparent::TRANSPARENT_NOTE_SERIALIZED_SIZE;
+
 use kelvin::{ByteHash, Content, Sink, Source};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -15,6 +19,39 @@ pub enum NoteVariant {
 }
 
 impl NoteVariant {
+    /// Decode a note from its canonical encoding, as created by [`NoteVariant::to_bytes`]
+    ///
+    /// The non-canonical encodings of the points, the identity and the points of small order are
+    /// rejected, as well as trailing bytes, so `note.to_bytes()? == bytes` for every decoded note
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let size = match bytes.first() {
+            Some(0x00) => 1 + TRANSPARENT_NOTE_SERIALIZED_SIZE,
+            Some(0x01) => 1 + OBFUSCATED_NOTE_SERIALIZED_SIZE,
+            _ => return Err(Error::InvalidParameters),
+        };
+
+        if bytes.len() < size {
+            return Err(Error::InvalidParameters);
+        } else if bytes.len() > size {
+            return Err(Error::NonCanonicalEncoding);
+        }
+
+        let mut note = NoteVariant::default();
+        note.write(bytes)?;
+
+        Ok(note)
+    }
+
+    /// Canonical encoding of the note
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes = vec![0x00u8; 1 + OBFUSCATED_NOTE_SERIALIZED_SIZE];
+        let mut note = *self;
+        let n = note.read(bytes.as_mut_slice())?;
+        bytes.truncate(n);
+
+        Ok(bytes)
+    }
+
     /// Create a new transaction input item provided the secret key for the nullifier generation
     /// and value / blinding factor decrypt
     pub fn to_transaction_input(
@@ -172,6 +209,20 @@ impl Note for NoteVariant {
         }
     }
 
+    fn unlock_height(&self) -> u64 {
+        match self {
+            NoteVariant::Transparent(note) => note.unlock_height(),
+            NoteVariant::Obfuscated(note) => note.unlock_height(),
+        }
+    }
+
+    fn set_unlock_height(&mut self, unlock_height: u64) {
+        match self {
+            NoteVariant::Transparent(note) => note.set_unlock_height(unlock_height),
+            NoteVariant::Obfuscated(note) => note.set_unlock_height(unlock_height),
+        }
+    }
+
     fn nonce(&self) -> &Nonce {
         match self {
             NoteVariant::Transparent(note) => note.nonce(),
@@ -193,7 +244,7 @@ impl Note for NoteVariant {
         }
     }
 
-    fn value(&self, vk: Option<&ViewKey>) -> u64 {
+    fn value(&self, vk: Option<&ViewKey>) -> Result<u64, Error> {
         match self {
             NoteVariant::Transparent(note) => note.value(vk),
             NoteVariant::Obfuscated(note) => note.value(vk),
@@ -207,6 +258,20 @@ impl Note for NoteVariant {
         }
     }
 
+    fn asset(&self, vk: Option<&ViewKey>) -> Result<u64, Error> {
+        match self {
+            NoteVariant::Transparent(note) => note.asset(vk),
+            NoteVariant::Obfuscated(note) => note.asset(vk),
+        }
+    }
+
+    fn encrypted_asset(&self) -> Option<&[u8; 24]> {
+        match self {
+            NoteVariant::Transparent(note) => note.encrypted_asset(),
+            NoteVariant::Obfuscated(note) => note.encrypted_asset(),
+        }
+    }
+
     fn value_commitment(&self) -> &JubJubExtended {
         match self {
             NoteVariant::Transparent(note) => note.value_commitment(),
@@ -227,6 +292,13 @@ impl Note for NoteVariant {
             NoteVariant::Obfuscated(note) => note.encrypted_blinding_factor(),
         }
     }
+
+    fn encrypted_memo(&self) -> &[u8; ENCRYPTED_MEMO_SIZE] {
+        match self {
+            NoteVariant::Transparent(note) => note.encrypted_memo(),
+            NoteVariant::Obfuscated(note) => note.encrypted_memo(),
+        }
+    }
 }
 
 impl<H: ByteHash> Content<H> for NoteVariant {
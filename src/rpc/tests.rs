@@ -1,8 +1,10 @@
 use crate::{
-    db, rpc, MerkleProofProvider, NoteGenerator, NoteVariant, ObfuscatedNote, SecretKey,
-    Transaction, TransactionItem, TransparentNote,
+    db, rpc, BlsScalar, Error, JubJubAffine, JubJubExtended, MerkleProofProvider, NoteGenerator,
+    NoteVariant, ObfuscatedNote, SecretKey, Transaction, TransactionItem, TransparentNote,
 };
 
+use std::convert::TryFrom;
+
 use kelvin::Blake2b;
 
 #[test]
@@ -71,3 +73,27 @@ fn rpc_transaction() {
     assert_eq!(3, transaction.fee().value());
     transaction.verify().unwrap();
 }
+
+#[test]
+fn rpc_strict_point() {
+    let pk = SecretKey::default().public_key();
+
+    let point: rpc::CompressedPoint = (*pk.A()).into();
+    assert_eq!(*pk.A(), JubJubExtended::try_from(point).unwrap());
+
+    let identity = rpc::CompressedPoint {
+        y: JubJubAffine::identity().to_bytes().to_vec(),
+    };
+    assert!(matches!(
+        JubJubExtended::try_from(identity),
+        Err(Error::IdentityPoint)
+    ));
+
+    let small_order = rpc::CompressedPoint {
+        y: (-BlsScalar::one()).to_bytes().to_vec(),
+    };
+    assert!(matches!(
+        JubJubAffine::try_from(small_order),
+        Err(Error::SmallOrderPoint)
+    ));
+}
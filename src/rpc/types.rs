@@ -92,7 +92,7 @@ impl TryFrom<rpc::CompressedPoint> for JubJubExtended {
     type Error = Error;
 
     fn try_from(p: rpc::CompressedPoint) -> Result<JubJubExtended, Error> {
-        utils::deserialize_compressed_jubjub(p.y.as_slice())
+        utils::deserialize_strict_jubjub(p.y.as_slice())
     }
 }
 
@@ -100,7 +100,7 @@ impl TryFrom<rpc::CompressedPoint> for JubJubAffine {
     type Error = Error;
 
     fn try_from(p: rpc::CompressedPoint) -> Result<JubJubAffine, Error> {
-        Ok(JubJubAffine::from(utils::deserialize_compressed_jubjub(
+        Ok(JubJubAffine::from(utils::deserialize_strict_jubjub(
             p.y.as_slice(),
         )?))
     }
@@ -1,5 +1,5 @@
 use crate::{
-    crypto, db, rpc, BlsScalar, Error, JubJubScalar, MerkleProofProvider, Nonce, Note,
+    crypto, db, rpc, utils, BlsScalar, Error, JubJubScalar, MerkleProofProvider, Nonce, Note,
     NoteGenerator, NoteVariant, Nullifier, PublicKey, SecretKey, TransparentNote,
 };
 
@@ -9,15 +9,23 @@ use std::fmt;
 use std::io::{self, Read, Write};
 
 use kelvin::ByteHash;
+use subtle::{Choice, ConstantTimeEq};
+
+lazy_static::lazy_static! {
+    static ref DEFAULT_INPUT: TransactionInput = TransactionInput::dummy();
+    static ref DEFAULT_OUTPUT: TransactionOutput = TransactionOutput::dummy();
+}
 
 /// A transaction item constains sensitive data for a proof creation, and must be obfuscated before
 /// network propagation.
 ///
-/// The secret is required on this structure for the proof generation
+/// The secret is required on this structure for the proof generation. The secret data is wiped
+/// from memory when the item is dropped or cleared, so the items are not `Copy`
 pub trait TransactionItem:
-    fmt::Debug + Default + Clone + Copy + PartialEq + Eq + PartialOrd + Ord + io::Read + io::Write
+    fmt::Debug + Default + Clone + PartialEq + Eq + PartialOrd + Ord + io::Read + io::Write
 {
     fn note(&self) -> &NoteVariant;
+    fn asset(&self) -> u64;
     fn value(&self) -> u64;
     fn blinding_factor(&self) -> &JubJubScalar;
 
@@ -31,19 +39,74 @@ pub trait TransactionItem:
     fn clear_sensitive_info(&mut self);
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Clone)]
 pub struct TransactionInput {
     note: NoteVariant,
+    asset: u64,
     value: u64,
     blinding_factor: JubJubScalar,
     pub nullifier: Nullifier,
     pub sk: SecretKey,
     pub merkle_opening: crypto::MerkleProof,
     pub merkle_root: BlsScalar,
+    /// Unlock height of the spent note, as declared by the spender
+    pub unlock_height: u64,
+}
+
+impl ConstantTimeEq for TransactionInput {
+    /// The public fields are compared with regular equality, and the secret fields in constant
+    /// time
+    fn ct_eq(&self, other: &Self) -> Choice {
+        let public = self.note == other.note
+            && self.nullifier == other.nullifier
+            && self.merkle_opening == other.merkle_opening
+            && self.merkle_root == other.merkle_root
+            && self.unlock_height == other.unlock_height;
+
+        Choice::from(public as u8)
+            & self.asset.ct_eq(&other.asset)
+            & self.value.ct_eq(&other.value)
+            & self.blinding_factor.ct_eq(&other.blinding_factor)
+            & self.sk.ct_eq(&other.sk)
+    }
+}
+
+impl PartialEq for TransactionInput {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(&other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for TransactionInput {}
+
+impl Drop for TransactionInput {
+    fn drop(&mut self) {
+        utils::wipe(&mut self.asset);
+        utils::wipe(&mut self.value);
+        utils::wipe(&mut self.blinding_factor);
+    }
+}
+
+impl fmt::Debug for TransactionInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransactionInput")
+            .field("note", &self.note)
+            .field("nullifier", &self.nullifier)
+            .field("merkle_root", &self.merkle_root)
+            .field("unlock_height", &self.unlock_height)
+            .finish()
+    }
 }
 
 impl Default for TransactionInput {
+    /// Clone of a single dummy input, created once
     fn default() -> Self {
+        DEFAULT_INPUT.clone()
+    }
+}
+
+impl TransactionInput {
+    fn dummy() -> Self {
         let sk = SecretKey::from(&b"default-tx-input"[..]);
         let pk = sk.public_key();
         let value = 0;
@@ -64,25 +127,32 @@ impl TransactionInput {
     pub fn new(
         note: NoteVariant,
         nullifier: Nullifier,
+        asset: u64,
         value: u64,
         blinding_factor: JubJubScalar,
         sk: SecretKey,
         merkle_opening: crypto::MerkleProof,
         merkle_root: BlsScalar,
     ) -> Self {
+        let unlock_height = note.unlock_height();
+
         Self {
             note,
             nullifier,
+            asset,
             value,
             blinding_factor,
             sk,
             merkle_opening,
             merkle_root,
+            unlock_height,
         }
     }
 
-    pub fn obfuscated(nullifier: Nullifier, merkle_root: BlsScalar) -> Self {
+    /// Create an input with only the public data, as propagated to the network
+    pub fn obfuscated(nullifier: Nullifier, merkle_root: BlsScalar, unlock_height: u64) -> Self {
         let note = Default::default();
+        let asset = Default::default();
         let value = Default::default();
         let blinding_factor = Default::default();
         let sk = Default::default();
@@ -91,11 +161,13 @@ impl TransactionInput {
         Self {
             note,
             nullifier,
+            asset,
             value,
             blinding_factor,
             sk,
             merkle_opening,
             merkle_root,
+            unlock_height,
         }
     }
 
@@ -116,6 +188,7 @@ impl TransactionInput {
         let mut txi = TransactionInput::default();
         txi.nullifier = item.nullifier.unwrap().try_into()?;
         txi.merkle_root = item.merkle_root.unwrap().try_into()?;
+        txi.unlock_height = item.unlock_height;
 
         Ok(txi)
     }
@@ -142,6 +215,10 @@ impl TransactionItem for TransactionInput {
         &self.note
     }
 
+    fn asset(&self) -> u64 {
+        self.asset
+    }
+
     fn value(&self) -> u64 {
         self.value
     }
@@ -159,24 +236,73 @@ impl TransactionItem for TransactionInput {
     }
 
     fn clear_sensitive_info(&mut self) {
+        utils::wipe(&mut self.asset);
+        utils::wipe(&mut self.value);
+        utils::wipe(&mut self.blinding_factor);
+
+        // The replaced secret key is wiped on drop
         self.note = NoteVariant::default();
-        self.value = 0;
-        self.blinding_factor = JubJubScalar::zero();
         self.sk = SecretKey::default();
         self.merkle_opening = crypto::MerkleProof::default();
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Clone)]
 pub struct TransactionOutput {
     pub note: NoteVariant,
+    pub asset: u64,
     pub value: u64,
     pub blinding_factor: JubJubScalar,
     pub pk: PublicKey,
 }
 
+impl ConstantTimeEq for TransactionOutput {
+    /// The public fields are compared with regular equality, and the secret fields in constant
+    /// time
+    fn ct_eq(&self, other: &Self) -> Choice {
+        let public = self.note == other.note && self.pk == other.pk;
+
+        Choice::from(public as u8)
+            & self.asset.ct_eq(&other.asset)
+            & self.value.ct_eq(&other.value)
+            & self.blinding_factor.ct_eq(&other.blinding_factor)
+    }
+}
+
+impl PartialEq for TransactionOutput {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(&other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for TransactionOutput {}
+
+impl Drop for TransactionOutput {
+    fn drop(&mut self) {
+        utils::wipe(&mut self.asset);
+        utils::wipe(&mut self.value);
+        utils::wipe(&mut self.blinding_factor);
+    }
+}
+
+impl fmt::Debug for TransactionOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransactionOutput")
+            .field("note", &self.note)
+            .field("pk", &self.pk)
+            .finish()
+    }
+}
+
 impl Default for TransactionOutput {
+    /// Clone of a single dummy output, created once
     fn default() -> Self {
+        DEFAULT_OUTPUT.clone()
+    }
+}
+
+impl TransactionOutput {
+    fn dummy() -> Self {
         let sk = SecretKey::from(&b"default-tx-input"[..]);
         let pk = sk.public_key();
         let value = 0;
@@ -193,12 +319,14 @@ impl Default for TransactionOutput {
 impl TransactionOutput {
     pub fn new(
         note: NoteVariant,
+        asset: u64,
         value: u64,
         blinding_factor: JubJubScalar,
         pk: PublicKey,
     ) -> Self {
         Self {
             note,
+            asset,
             value,
             blinding_factor,
             pk,
@@ -231,6 +359,10 @@ impl TransactionItem for TransactionOutput {
         &self.note
     }
 
+    fn asset(&self) -> u64 {
+        self.asset
+    }
+
     fn value(&self) -> u64 {
         self.value
     }
@@ -248,8 +380,9 @@ impl TransactionItem for TransactionOutput {
     }
 
     fn clear_sensitive_info(&mut self) {
-        self.value = 0;
-        self.blinding_factor = JubJubScalar::zero();
+        utils::wipe(&mut self.asset);
+        utils::wipe(&mut self.value);
+        utils::wipe(&mut self.blinding_factor);
         self.pk = PublicKey::default();
     }
 }
@@ -288,6 +421,7 @@ impl From<TransactionInput> for rpc::TransactionInput {
         rpc::TransactionInput {
             merkle_root,
             nullifier,
+            unlock_height: item.unlock_height,
         }
     }
 }
@@ -312,19 +446,7 @@ impl TryFrom<rpc::TransactionInput> for TransactionInput {
         let nullifier = txi.nullifier.unwrap_or_default().try_into()?;
         let merkle_root = txi.merkle_root.unwrap_or_default().try_into()?;
 
-        let merkle_opening = Default::default();
-        let value = Default::default();
-        let blinding_factor = Default::default();
-
-        Ok(Self::new(
-            NoteVariant::default(),
-            nullifier,
-            value,
-            blinding_factor,
-            SecretKey::default(),
-            merkle_opening,
-            merkle_root,
-        ))
+        Ok(Self::obfuscated(nullifier, merkle_root, txi.unlock_height))
     }
 }
 
@@ -347,7 +469,13 @@ impl TryFrom<rpc::TransactionOutput> for TransactionOutput {
             .ok_or(Error::InvalidParameters)?
             .try_into()?;
 
-        Ok(TransactionOutput::new(note, txo.value, blinding_factor, pk))
+        Ok(TransactionOutput::new(
+            note,
+            txo.asset,
+            txo.value,
+            blinding_factor,
+            pk,
+        ))
     }
 }
 
@@ -358,6 +486,7 @@ impl From<TransactionOutput> for rpc::TransactionOutput {
             pk: Some(item.pk.into()),
             value: item.value,
             blinding_factor: Some((*item.blinding_factor()).into()),
+            asset: item.asset,
         }
     }
 }
@@ -1,12 +1,13 @@
 use crate::{
-    crypto, db, rpc, utils, zk, BlsScalar, Error, Note, NoteGenerator, ObfuscatedNote, PublicKey,
-    SecretKey, TransparentNote,
+    crypto, db, rpc, utils, zk, BlsScalar, Error, MerkleProofProvider, Note, NoteGenerator,
+    NoteVariant, ObfuscatedNote, PublicKey, SecretKey, TransparentNote, NATIVE_ASSET,
 };
 
 use std::convert::TryFrom;
+use std::fmt;
 use std::io::{self, Read, Write};
+use std::iter;
 use std::mem;
-use std::{fmt, ptr};
 
 use dusk_plonk::proof_system::Proof;
 use kelvin::ByteHash;
@@ -23,18 +24,16 @@ pub const MAX_OUTPUT_NOTES_PER_TRANSACTION: usize = 2;
 /// Maximum allowed number of notes per transaction.
 
 /// Serialized bytes size
-pub const TX_SERIALIZED_SIZE: usize = 1684;
+pub const TX_SERIALIZED_SIZE: usize = 1884;
 
 pub use item::{TransactionInput, TransactionItem, TransactionOutput};
 
-lazy_static::lazy_static! {
-    static ref DEFAULT_INPUT: TransactionInput = TransactionInput::default();
-    static ref DEFAULT_OUTPUT: TransactionOutput = TransactionOutput::default();
-}
-
 /// Transaction item definitions
 pub mod item;
 
+#[cfg(test)]
+mod tests;
+
 /// A phoenix transaction
 pub struct Transaction {
     fee: TransactionOutput,
@@ -67,11 +66,11 @@ impl Clone for Transaction {
 impl Default for Transaction {
     fn default() -> Self {
         Self {
-            fee: *DEFAULT_OUTPUT,
+            fee: TransactionOutput::default(),
             idx_inputs: 0,
-            inputs: [*DEFAULT_INPUT; MAX_INPUT_NOTES_PER_TRANSACTION],
+            inputs: Default::default(),
             idx_outputs: 0,
-            outputs: [*DEFAULT_OUTPUT; MAX_OUTPUT_NOTES_PER_TRANSACTION],
+            outputs: Default::default(),
             proof: None,
             public_inputs: None,
         }
@@ -79,9 +78,17 @@ impl Default for Transaction {
 }
 
 impl Read for Transaction {
-    fn read(&mut self, mut buf: &mut [u8]) -> io::Result<usize> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         self.clear_sensitive_info();
 
+        self.serialize_to(buf)
+    }
+}
+
+impl Transaction {
+    /// Serialize the public data of the transaction, as [`Read`] does, without clearing its
+    /// secret data. Return the number of written bytes.
+    fn serialize_to(&self, mut buf: &mut [u8]) -> io::Result<usize> {
         let mut n = 0;
 
         // Serialize proof
@@ -94,7 +101,7 @@ impl Read for Transaction {
             buf = &mut buf[b..];
         }
 
-        // Serialize tx inputs (merkle root and nullifier)
+        // Serialize tx inputs (merkle root, nullifier and unlock height)
         let inputs = self.idx_inputs.to_le_bytes();
         let b = (&inputs[..]).read(buf)?;
         n += b;
@@ -116,6 +123,10 @@ impl Read for Transaction {
                 .map_err::<io::Error, _>(|e| e.into())?;
             n += utils::BLS_SCALAR_SERIALIZED_SIZE;
             buf = &mut buf[utils::BLS_SCALAR_SERIALIZED_SIZE..];
+
+            let b = (&self.inputs[i].unlock_height.to_le_bytes()[..]).read(buf)?;
+            n += b;
+            buf = &mut buf[b..];
         }
 
         // Serialize tx outputs
@@ -125,12 +136,12 @@ impl Read for Transaction {
         buf = &mut buf[b..];
 
         for i in 0..MAX_OUTPUT_NOTES_PER_TRANSACTION {
-            let b = self.outputs[i].read(buf)?;
+            let b = (*self.outputs[i].note()).read(buf)?;
             n += b;
             buf = &mut buf[b..];
         }
 
-        let b = self.fee.read(buf)?;
+        let b = (*self.fee.note()).read(buf)?;
         n += b;
 
         Ok(n)
@@ -151,7 +162,7 @@ impl Write for Transaction {
         n += b;
         buf = &buf[b..];
 
-        // Deserialize tx inputs (merkle root and nullifier)
+        // Deserialize tx inputs (merkle root, nullifier and unlock height)
         let mut inputs = 0usize.to_le_bytes();
         let b = (&mut inputs[..]).write(buf)?;
         self.idx_inputs = usize::from_le_bytes(inputs);
@@ -177,7 +188,14 @@ impl Write for Transaction {
             n += utils::BLS_SCALAR_SERIALIZED_SIZE;
             buf = &buf[utils::BLS_SCALAR_SERIALIZED_SIZE..];
 
-            self.inputs[i] = TransactionInput::obfuscated(nullifier.into(), merkle_root);
+            let mut unlock_height = 0u64.to_le_bytes();
+            let b = (&mut unlock_height[..]).write(buf)?;
+            let unlock_height = u64::from_le_bytes(unlock_height);
+            n += b;
+            buf = &buf[b..];
+
+            self.inputs[i] =
+                TransactionInput::obfuscated(nullifier.into(), merkle_root, unlock_height);
         }
 
         // Deserialize tx outputs
@@ -271,6 +289,15 @@ impl PartialEq for Transaction {
 impl Eq for Transaction {}
 
 impl Transaction {
+    /// Length, in bytes, of the serialization of the transaction with [`Read`]
+    ///
+    /// The transaction is not mutated, so its secret data is kept.
+    pub fn serialized_size(&self) -> Result<usize, Error> {
+        let mut bytes = [0x00u8; TX_SERIALIZED_SIZE];
+
+        Ok(self.serialize_to(&mut bytes)?)
+    }
+
     /// Perform a hash of the inputs, outputs and fee
     pub fn hash(&self) -> BlsScalar {
         // TODO - Maybe improve?
@@ -280,39 +307,63 @@ impl Transaction {
 
         hash[0] = self.fee.hash();
 
-        let mut items = [TransactionInput::default(); MAX_INPUT_NOTES_PER_TRANSACTION];
+        let mut inputs: Vec<&TransactionInput> = self.inputs().iter().collect();
+        inputs.sort();
+        inputs.iter().map(|item| item.note().hash()).for_each(|h| {
+            hash[i] = h;
+            i += 1;
+        });
 
-        let max_idx = self.idx_inputs;
-        if max_idx > 0 {
-            items.copy_from_slice(&self.inputs);
-            (&mut items[0..max_idx]).sort();
+        let mut outputs: Vec<&TransactionOutput> = self.outputs().iter().collect();
+        outputs.sort();
+        outputs.iter().map(|item| item.note().hash()).for_each(|h| {
+            hash[i] = h;
+            i += 1;
+        });
 
-            items[0..max_idx]
-                .iter()
-                .map(|item| item.note().hash())
-                .for_each(|h| {
-                    hash[i] = h;
-                    i += 1;
-                });
-        }
+        crypto::domain_hash(crypto::HashDomain::Transaction, &hash[0..i])
+    }
 
-        let mut items = [TransactionOutput::default(); MAX_OUTPUT_NOTES_PER_TRANSACTION];
+    /// Hash of the data of the transaction propagated to the network: the nullifiers and merkle
+    /// roots of the inputs, the output notes and the fee note
+    ///
+    /// The input notes are not part of it, so the hash is not affected by
+    /// [`Transaction::clear_sensitive_info`]
+    fn signature_hash(&self) -> BlsScalar {
+        let mut inputs: Vec<(BlsScalar, BlsScalar)> = self
+            .inputs()
+            .iter()
+            .map(|i| (*i.nullifier().s(), i.merkle_root))
+            .collect();
+        inputs.sort();
+
+        let mut outputs: Vec<BlsScalar> = self.outputs().iter().map(|o| o.note().hash()).collect();
+        outputs.sort();
+
+        let data: Vec<BlsScalar> = iter::once(self.fee.note().hash())
+            .chain(
+                inputs
+                    .into_iter()
+                    .flat_map(|(n, r)| iter::once(n).chain(iter::once(r))),
+            )
+            .chain(outputs.into_iter())
+            .collect();
 
-        let max_idx = self.idx_outputs;
-        if max_idx > 0 {
-            items.copy_from_slice(&self.outputs);
-            (&mut items[0..max_idx]).sort();
+        crypto::domain_hash(crypto::HashDomain::TransactionSignature, data.as_slice())
+    }
 
-            items[0..max_idx]
-                .iter()
-                .map(|item| item.note().hash())
-                .for_each(|h| {
-                    hash[i] = h;
-                    i += 1;
-                });
-        }
+    /// Sign the public data of the transaction with the one-time secret of an owned note,
+    /// authorising actions outside of the proof
+    ///
+    /// The signed data is the same before and after [`Transaction::clear_sensitive_info`], so the
+    /// transaction can be signed at any point before it is propagated
+    pub fn sign<N: Note>(&self, sk: &SecretKey, note: &N) -> crypto::Signature {
+        note.sign(sk, &self.signature_hash().to_bytes()[..])
+    }
 
-        crypto::sponge_hash(&hash[0..i])
+    /// Verify a signature created with [`Transaction::sign`] by the owner of the provided note
+    pub fn verify_signature<N: Note>(&self, note: &N, signature: &crypto::Signature) -> bool {
+        note.verify_signature(&self.signature_hash().to_bytes()[..], signature)
     }
 
     /// Append an input to the transaction
@@ -383,42 +434,32 @@ impl Transaction {
 
     /// Remove a specified transaction input and return it, if present
     pub fn remove_input(&mut self, idx: usize) -> Option<TransactionInput> {
-        if self.idx_inputs == 0 || idx >= self.idx_inputs {
+        if idx >= self.idx_inputs {
             return None;
-        } else if self.idx_inputs == 1 {
-            self.idx_inputs = 0;
-            return Some(self.inputs[0]);
         }
 
         self.idx_inputs -= 1;
-        let src = (&mut self.inputs[self.idx_inputs]) as *mut TransactionInput;
-        let dst = (&mut self.inputs[idx]) as *mut TransactionInput;
-        unsafe {
-            ptr::swap(src, dst);
-        }
-        self.inputs[self.idx_inputs] = *DEFAULT_INPUT;
+        self.inputs.swap(idx, self.idx_inputs);
 
-        Some(self.inputs[self.idx_inputs])
+        Some(mem::replace(
+            &mut self.inputs[self.idx_inputs],
+            TransactionInput::default(),
+        ))
     }
 
     /// Remove a specified transaction output and return it, if present
     pub fn remove_output(&mut self, idx: usize) -> Option<TransactionOutput> {
-        if self.idx_outputs == 0 || idx >= self.idx_outputs {
+        if idx >= self.idx_outputs {
             return None;
-        } else if self.idx_outputs == 1 {
-            self.idx_outputs = 0;
-            return Some(self.outputs[0]);
         }
 
         self.idx_outputs -= 1;
-        let src = (&mut self.outputs[self.idx_outputs]) as *mut TransactionOutput;
-        let dst = (&mut self.outputs[idx]) as *mut TransactionOutput;
-        unsafe {
-            ptr::swap(src, dst);
-        }
-        self.outputs[self.idx_outputs] = *DEFAULT_OUTPUT;
+        self.outputs.swap(idx, self.idx_outputs);
 
-        Some(self.outputs[self.idx_outputs])
+        Some(mem::replace(
+            &mut self.outputs[self.idx_outputs],
+            TransactionOutput::default(),
+        ))
     }
 
     /// Sort the inputs and outputs
@@ -443,7 +484,8 @@ impl Transaction {
     ///
     /// Depends on the secret data of the transaction items
     ///
-    /// The transaction items will be sorted for verification correctness
+    /// The transaction items will be sorted for verification correctness. The fee must be paid in
+    /// the [`NATIVE_ASSET`]
     pub fn prove(&mut self) -> Result<(), Error> {
         if self.idx_inputs > MAX_INPUT_NOTES_PER_TRANSACTION
             || self.idx_outputs > MAX_OUTPUT_NOTES_PER_TRANSACTION
@@ -451,6 +493,10 @@ impl Transaction {
             return Err(Error::MaximumNotes);
         }
 
+        if self.fee.asset() != NATIVE_ASSET {
+            return Err(Error::InvalidParameters);
+        }
+
         self.recalculate_pi();
 
         let proof = zk::prove(self);
@@ -471,6 +517,8 @@ impl Transaction {
 
     /// Remove all the sensitive info from the transaction used to build the zk proof so it can be
     /// safely broadcasted
+    ///
+    /// The values, blinding factors and secret keys of the items are wiped from memory
     pub fn clear_sensitive_info(&mut self) {
         self.inputs
             .iter_mut()
@@ -506,6 +554,127 @@ impl Transaction {
         }
     }
 
+    /// Create a transaction that shields `value` of the [`NATIVE_ASSET`], spending transparent
+    /// notes owned by `sk` into an obfuscated note to the recipient, and paying `fee`.
+    ///
+    /// The change, if any, is sent back to the owner as a transparent note.
+    ///
+    /// Will prove and verify the created transaction.
+    pub fn shield<P: MerkleProofProvider>(
+        state: &P,
+        height: u64,
+        sk: &SecretKey,
+        notes: &[TransparentNote],
+        recipient: &PublicKey,
+        value: u64,
+        fee: u64,
+    ) -> Result<Self, Error> {
+        Self::convert::<_, ObfuscatedNote, TransparentNote, _>(
+            state, height, sk, notes, recipient, value, fee,
+        )
+    }
+
+    /// Create a transaction that unshields `value` of the [`NATIVE_ASSET`], spending obfuscated
+    /// notes owned by `sk` into a transparent note to the recipient, and paying `fee`.
+    ///
+    /// The change, if any, is sent back to the owner as an obfuscated note.
+    ///
+    /// Will prove and verify the created transaction.
+    pub fn unshield<P: MerkleProofProvider>(
+        state: &P,
+        height: u64,
+        sk: &SecretKey,
+        notes: &[ObfuscatedNote],
+        recipient: &PublicKey,
+        value: u64,
+        fee: u64,
+    ) -> Result<Self, Error> {
+        Self::convert::<_, TransparentNote, ObfuscatedNote, _>(
+            state, height, sk, notes, recipient, value, fee,
+        )
+    }
+
+    /// Spend the notes into an output of type `O` to the recipient, and a change of type `C` to
+    /// the owner
+    ///
+    /// The notes are rejected before proving if `sk` doesn't own them
+    /// ([`Error::KeyMismatch`]), if one of them is provided twice ([`Error::DoubleSpending`]),
+    /// or if they are still locked at `height` ([`Error::NoteLocked`]).
+    fn convert<I, O, C, P>(
+        state: &P,
+        height: u64,
+        sk: &SecretKey,
+        notes: &[I],
+        recipient: &PublicKey,
+        value: u64,
+        fee: u64,
+    ) -> Result<Self, Error>
+    where
+        I: NoteGenerator + Copy,
+        O: NoteGenerator,
+        C: NoteGenerator,
+        P: MerkleProofProvider,
+    {
+        let vk = sk.view_key();
+        let amount = value.checked_add(fee).ok_or(Error::InvalidParameters)?;
+
+        let mut transaction = Transaction::default();
+        let mut total = 0u64;
+
+        for note in notes {
+            if !note.is_owned_by(&vk) {
+                return Err(Error::KeyMismatch);
+            }
+
+            if note.is_locked(height) {
+                return Err(Error::NoteLocked);
+            }
+
+            if note.asset(Some(&vk))? != NATIVE_ASSET {
+                return Err(Error::InvalidParameters);
+            }
+
+            let variant: NoteVariant = (*note).into();
+            let merkle_opening = state.opening(&variant)?;
+            let input = note.to_transaction_input(merkle_opening, sk.clone())?;
+
+            if transaction
+                .inputs()
+                .iter()
+                .any(|i| i.nullifier() == input.nullifier())
+            {
+                return Err(Error::DoubleSpending);
+            }
+
+            total = total
+                .checked_add(input.value())
+                .ok_or(Error::InvalidParameters)?;
+            transaction.push_input(input)?;
+        }
+
+        let change = total
+            .checked_sub(amount)
+            .ok_or(Error::InsufficientBalance)?;
+
+        let (note, blinding_factor) = O::output(recipient, value);
+        transaction.push_output(note.to_transaction_output(value, blinding_factor, *recipient))?;
+
+        if change > 0 {
+            let pk = sk.public_key();
+            let (note, blinding_factor) = C::output(&pk, change);
+            transaction.push_output(note.to_transaction_output(change, blinding_factor, pk))?;
+        }
+
+        let pk = PublicKey::default();
+        let (note, blinding_factor) = TransparentNote::output(&pk, fee);
+        transaction.set_fee(note.to_transaction_output(fee, blinding_factor, pk));
+
+        transaction.prove()?;
+        transaction.verify()?;
+
+        Ok(transaction)
+    }
+
     /// Create a new transaction from a set of inputs/outputs defined by a rpc source.
     ///
     /// Will prove and verify the created transaction.
@@ -616,7 +785,7 @@ impl TryFrom<Transaction> for rpc::Transaction {
             .iter()
             .filter_map(|i| {
                 if i.value() > 0 {
-                    Some((*i).into())
+                    Some(i.clone().into())
                 } else {
                     None
                 }
@@ -628,14 +797,14 @@ impl TryFrom<Transaction> for rpc::Transaction {
             .iter()
             .filter_map(|o| {
                 if o.value() > 0 {
-                    Some((*o).into())
+                    Some(o.clone().into())
                 } else {
                     None
                 }
             })
             .collect();
 
-        let fee = Some(tx.fee.into());
+        let fee = Some(tx.fee.clone().into());
 
         let proof = tx
             .proof()
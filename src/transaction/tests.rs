@@ -0,0 +1,252 @@
+use crate::{
+    crypto, db, Error, JubJubScalar, Note, NoteGenerator, NoteType, ObfuscatedNote, SecretKey,
+    Transaction, TransactionItem, TransparentNote, TX_SERIALIZED_SIZE,
+};
+
+use std::convert::TryFrom;
+use std::io::Read;
+
+use kelvin::Blake2b;
+
+fn transaction(sk: &SecretKey) -> Transaction {
+    let mut tx = Transaction::default();
+
+    let note = TransparentNote::output(&sk.public_key(), 100).0;
+    let merkle_opening = crypto::MerkleProof::mock(note.hash());
+    tx.push_input(
+        note.to_transaction_input(merkle_opening, sk.clone())
+            .unwrap(),
+    )
+    .unwrap();
+
+    let pk = SecretKey::default().public_key();
+    let (note, blinding_factor) = ObfuscatedNote::output(&pk, 95);
+    tx.push_output(note.to_transaction_output(95, blinding_factor, pk))
+        .unwrap();
+
+    let (note, blinding_factor) = TransparentNote::output(&pk, 5);
+    tx.set_fee(note.to_transaction_output(5, blinding_factor, pk));
+
+    tx
+}
+
+#[test]
+fn clear_sensitive_info() {
+    let sk = SecretKey::default();
+    let mut tx = transaction(&sk);
+
+    tx.clear_sensitive_info();
+
+    let input = &tx.inputs()[0];
+    assert_eq!(0, input.value());
+    assert_eq!(&JubJubScalar::zero(), input.blinding_factor());
+    assert_ne!(&sk, input.sk());
+
+    let output = &tx.outputs()[0];
+    assert_eq!(0, output.value());
+    assert_eq!(&JubJubScalar::zero(), output.blinding_factor());
+}
+
+#[test]
+fn serialized_size() {
+    let sk = SecretKey::default();
+    let mut tx = transaction(&sk);
+    tx.prove().unwrap();
+
+    let hash = tx.hash();
+    let size = tx.serialized_size().unwrap();
+    assert_eq!(hash, tx.hash());
+    assert_eq!(95, tx.outputs()[0].value());
+
+    let mut bytes = [0x00u8; TX_SERIALIZED_SIZE];
+    assert_eq!(size, tx.read(&mut bytes).unwrap());
+}
+
+#[test]
+fn remove_items() {
+    let sk = SecretKey::default();
+    let mut tx = transaction(&sk);
+
+    let nullifier = *tx.inputs()[0].nullifier();
+    let output = tx.outputs()[0].note().hash();
+
+    let input = tx.remove_input(0).unwrap();
+    assert_eq!(&nullifier, input.nullifier());
+    assert_eq!(&sk, input.sk());
+    assert!(tx.inputs().is_empty());
+    assert!(tx.remove_input(0).is_none());
+
+    assert_eq!(output, tx.remove_output(0).unwrap().note().hash());
+    assert!(tx.outputs().is_empty());
+}
+
+#[test]
+fn items_ct_eq() {
+    let sk = SecretKey::default();
+    let tx = transaction(&sk);
+
+    let input = tx.inputs()[0].clone();
+    assert_eq!(tx.inputs()[0], input);
+
+    let mut other = input.clone();
+    other.sk = SecretKey::default();
+    assert_ne!(input, other);
+
+    let mut output = tx.outputs()[0].clone();
+    assert_eq!(tx.outputs()[0], output);
+
+    output.blinding_factor += JubJubScalar::one();
+    assert_ne!(tx.outputs()[0], output);
+}
+
+#[test]
+fn transaction_signature() {
+    let sk = SecretKey::default();
+    let mut tx = transaction(&sk);
+    let note = tx.inputs()[0].note().clone();
+
+    // The signature doesn't depend on the sensitive info
+    let signature = tx.sign(&sk, &note);
+    tx.clear_sensitive_info();
+    assert!(tx.verify_signature(&note, &signature));
+    assert!(!tx.verify_signature(tx.outputs()[0].note(), &signature));
+
+    tx.set_fee_pk(SecretKey::default().public_key());
+    assert!(!tx.verify_signature(&note, &signature));
+}
+
+#[test]
+fn shield() {
+    let mut db = db::Db::<Blake2b>::default();
+
+    let sk = SecretKey::default();
+    let pk = sk.public_key();
+    let idx = db
+        .store_unspent_note(TransparentNote::output(&pk, 100).0.into())
+        .unwrap();
+    let note = TransparentNote::try_from(db.fetch_note(idx).unwrap()).unwrap();
+
+    let recipient = SecretKey::default();
+
+    match Transaction::shield(&db, 0, &sk, &[note], &recipient.public_key(), 100, 5) {
+        Err(Error::InsufficientBalance) => (),
+        _ => panic!("The notes don't cover the value and fee"),
+    }
+
+    match Transaction::shield(&db, 0, &sk, &[note, note], &recipient.public_key(), 100, 5) {
+        Err(Error::DoubleSpending) => (),
+        _ => panic!("The note is provided twice"),
+    }
+
+    match Transaction::shield(&db, 0, &recipient, &[note], &pk, 60, 5) {
+        Err(Error::KeyMismatch) => (),
+        _ => panic!("The note is not owned by the key"),
+    }
+
+    let mut locked = TransparentNote::output(&pk, 100).0;
+    locked.set_unlock_height(10);
+    let idx = db.store_unspent_note(locked.into()).unwrap();
+    let locked = TransparentNote::try_from(db.fetch_note(idx).unwrap()).unwrap();
+    match Transaction::shield(&db, 9, &sk, &[locked], &recipient.public_key(), 60, 5) {
+        Err(Error::NoteLocked) => (),
+        _ => panic!("The note is locked"),
+    }
+    assert!(Transaction::shield(&db, 10, &sk, &[locked], &recipient.public_key(), 60, 5).is_ok());
+
+    let tx = Transaction::shield(&db, 0, &sk, &[note], &recipient.public_key(), 60, 5).unwrap();
+    assert_eq!(2, tx.outputs().len());
+    assert_eq!(5, tx.fee().value());
+
+    // The proof is still a mock, so the balance is asserted on the items
+    let outputs: u64 = tx.outputs().iter().map(|o| o.value()).sum();
+    let inputs: u64 = tx.inputs().iter().map(|i| i.value()).sum();
+    assert_eq!(inputs, outputs + tx.fee().value());
+
+    let output = tx
+        .outputs()
+        .iter()
+        .find(|o| o.note().is_owned_by(&recipient.view_key()))
+        .unwrap();
+    assert_eq!(NoteType::Obfuscated, output.note().note());
+    assert_eq!(
+        60,
+        output.note().value(Some(&recipient.view_key())).unwrap()
+    );
+
+    let change = tx
+        .outputs()
+        .iter()
+        .find(|o| o.note().is_owned_by(&sk.view_key()))
+        .unwrap();
+    assert_eq!(NoteType::Transparent, change.note().note());
+    assert_eq!(35, change.note().value(None).unwrap());
+
+    db.store_transaction(&tx, 0).unwrap();
+}
+
+#[test]
+fn unshield() {
+    let mut db = db::Db::<Blake2b>::default();
+
+    let sk = SecretKey::default();
+    let pk = sk.public_key();
+    let idx = db
+        .store_unspent_note(ObfuscatedNote::output(&pk, 100).0.into())
+        .unwrap();
+    let note = ObfuscatedNote::try_from(db.fetch_note(idx).unwrap()).unwrap();
+
+    let recipient = SecretKey::default();
+
+    // The whole value is unshielded, so there is no change
+    let tx = Transaction::unshield(&db, 0, &sk, &[note], &recipient.public_key(), 95, 5).unwrap();
+    assert_eq!(1, tx.outputs().len());
+    assert_eq!(100, tx.outputs()[0].value() + tx.fee().value());
+
+    let output = &tx.outputs()[0];
+    assert_eq!(NoteType::Transparent, output.note().note());
+    assert!(output.note().is_owned_by(&recipient.view_key()));
+    assert_eq!(95, output.note().value(None).unwrap());
+
+    let tx = Transaction::unshield(&db, 0, &sk, &[note], &recipient.public_key(), 40, 5).unwrap();
+    let outputs: u64 = tx.outputs().iter().map(|o| o.value()).sum();
+    assert_eq!(100, outputs + tx.fee().value());
+
+    let change = tx
+        .outputs()
+        .iter()
+        .find(|o| o.note().is_owned_by(&sk.view_key()))
+        .unwrap();
+    assert_eq!(NoteType::Obfuscated, change.note().note());
+    assert_eq!(55, change.note().value(Some(&sk.view_key())).unwrap());
+
+    db.store_transaction(&tx, 0).unwrap();
+
+    // The spent note can't be unshielded again
+    let tx = Transaction::unshield(&db, 0, &sk, &[note], &recipient.public_key(), 40, 5).unwrap();
+    match db.store_transaction(&tx, 0) {
+        Err(Error::DoubleSpending) => (),
+        _ => panic!("The note is already spent"),
+    }
+}
+
+#[test]
+#[cfg(feature = "serde-support")]
+fn transaction_serde() {
+    let sk = SecretKey::default();
+    let tx = transaction(&sk);
+
+    let json = serde_json::to_string(&tx).unwrap();
+    let deserialized: Transaction = serde_json::from_str(json.as_str()).unwrap();
+
+    assert_eq!(json, serde_json::to_string(&deserialized).unwrap());
+    assert_eq!(
+        tx.inputs()[0].nullifier(),
+        deserialized.inputs()[0].nullifier()
+    );
+    assert_eq!(tx.outputs()[0].note(), deserialized.outputs()[0].note());
+    assert_eq!(5, deserialized.fee().value());
+
+    // No secret is serialized
+    assert_eq!(0, deserialized.outputs()[0].value());
+    assert!(!json.contains(&sk.to_string()));
+}
@@ -2,6 +2,7 @@ use crate::{BlsScalar, Error, JubJubAffine, JubJubExtended, JubJubScalar, Nonce,
 
 use std::io::{self, Read};
 use std::mem::{self, MaybeUninit};
+use std::sync::atomic::{self, Ordering};
 use std::{cmp, ptr};
 
 use kelvin::{ByteHash, Source};
@@ -23,6 +24,21 @@ pub(crate) unsafe fn lazy_static_maybeuninit_write<T>(p: &MaybeUninit<T>, v: T)
     p.write(v);
 }
 
+/// Overwrite the provided value with its default, preventing the write from being optimized out
+///
+/// Used to wipe secrets from memory before they are released
+pub fn wipe<T: Copy + Default>(value: &mut T) {
+    unsafe {
+        ptr::write_volatile(value, T::default());
+    }
+    atomic::compiler_fence(Ordering::SeqCst);
+}
+
+/// Overwrite the provided bytes with zeroes, preventing the write from being optimized out
+pub fn wipe_bytes(bytes: &mut [u8]) {
+    bytes.iter_mut().for_each(wipe);
+}
+
 /// Generate a random [`JubJubScalar`] from [`rand::thread_rng`]
 pub fn gen_random_scalar() -> JubJubScalar {
     gen_random_scalar_from_rng(&mut rand::thread_rng())
@@ -59,6 +75,49 @@ pub fn deserialize_compressed_jubjub(bytes: &[u8]) -> Result<JubJubExtended, Err
     Ok(JubJubExtended::from(result.unwrap()))
 }
 
+/// Deserialize a [`JubJubAffine`] from a slice of bytes, and convert it to [`JubJubExtended`]
+///
+/// Unlike [`deserialize_compressed_jubjub`], only the canonical encoding of a point is accepted,
+/// so the point serializes back to the same bytes. The identity and the points of small order are
+/// rejected
+pub fn deserialize_strict_jubjub(bytes: &[u8]) -> Result<JubJubExtended, Error> {
+    if bytes.len() < 32 {
+        return Err(Error::InvalidParameters);
+    }
+
+    let mut array = [0u8; 32];
+    array.copy_from_slice(&bytes[..32]);
+
+    // The encoding is the `v` coordinate, with the sign of `u` on the most significant bit
+    let mut v = array;
+    v[31] &= 0x7f;
+    if BlsScalar::from_bytes(&v).is_none().unwrap_u8() == 1 {
+        return Err(Error::NonCanonicalEncoding);
+    }
+
+    let result = JubJubAffine::from_bytes(array);
+    if result.is_none().unwrap_u8() == 1 {
+        return Err(Error::InvalidPoint);
+    }
+
+    // The points with `u = 0` decode with both signs
+    let point = result.unwrap();
+    if point.to_bytes() != array {
+        return Err(Error::NonCanonicalEncoding);
+    }
+
+    let point = JubJubExtended::from(point);
+    if point.is_identity().unwrap_u8() == 1 {
+        return Err(Error::IdentityPoint);
+    }
+
+    if point.is_small_order().unwrap_u8() == 1 {
+        return Err(Error::SmallOrderPoint);
+    }
+
+    Ok(point)
+}
+
 /// Deserialize a [`JubJubScalar`] from a slice of bytes
 pub fn deserialize_jubjub_scalar(bytes: &[u8]) -> Result<JubJubScalar, Error> {
     if bytes.len() < 32 {
@@ -119,7 +178,7 @@ pub fn kelvin_source_to_jubjub_projective<H: ByteHash>(
 
     source.read_exact(&mut p)?;
 
-    deserialize_compressed_jubjub(&p).map_err(|e| e.into())
+    deserialize_strict_jubjub(&p).map_err(|e| e.into())
 }
 
 /// Deserialize a [`Nonce`] from a [`Source`]
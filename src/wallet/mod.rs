@@ -0,0 +1,275 @@
+use crate::{
+    db, DbEvent, Error, MerkleProofProvider, Note, NoteGenerator, NoteVariant, ObfuscatedNote,
+    PublicKey, SecretKey, Transaction, TransactionOutput, TransparentNote, ViewKey,
+    MAX_INPUT_NOTES_PER_TRANSACTION, NATIVE_ASSET,
+};
+
+use std::collections::BTreeMap;
+
+use kelvin::ByteHash;
+
+#[cfg(test)]
+mod tests;
+
+/// Note owned by the view key of a [`WatchOnlyWallet`], with its decrypted asset and value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OwnedNote {
+    note: NoteVariant,
+    asset: u64,
+    value: u64,
+}
+
+impl OwnedNote {
+    /// Note as stored on the tree
+    pub fn note(&self) -> &NoteVariant {
+        &self.note
+    }
+
+    /// Decrypted asset id of the note
+    pub fn asset(&self) -> u64 {
+        self.asset
+    }
+
+    /// Decrypted value of the note
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+}
+
+/// Wallet that tracks the notes and balance of a [`ViewKey`], without the ability to spend them
+///
+/// The spends are prepared as [`TransactionTemplate`], completed and proven by the holder of the
+/// [`SecretKey`], for example on a cold storage device.
+///
+/// The nullifiers depend on the secret `b`, so the wallet can't detect the spends of its notes
+/// from the state. The notes are removed from the balance with [`WatchOnlyWallet::mark_spent`],
+/// once the completed transaction is stored.
+#[derive(Debug, Clone)]
+pub struct WatchOnlyWallet {
+    vk: ViewKey,
+    notes: BTreeMap<u64, OwnedNote>,
+    next_idx: u64,
+}
+
+impl WatchOnlyWallet {
+    /// Create an empty wallet for the provided view key
+    pub fn new(vk: ViewKey) -> Self {
+        WatchOnlyWallet {
+            vk,
+            notes: BTreeMap::new(),
+            next_idx: 0,
+        }
+    }
+
+    /// View key of the wallet
+    pub fn view_key(&self) -> &ViewKey {
+        &self.vk
+    }
+
+    /// Public key that receives the notes of the wallet
+    pub fn public_key(&self) -> PublicKey {
+        self.vk.public_key()
+    }
+
+    /// Scan the notes stored after the last scan. Return the number of new owned notes.
+    pub fn scan<H: ByteHash>(&mut self, db: &db::Db<H>) -> Result<usize, Error> {
+        let mut owned = 0;
+
+        loop {
+            let note = match db.fetch_note(self.next_idx) {
+                Ok(note) => note,
+                Err(Error::NotFound) => break,
+                Err(e) => return Err(e),
+            };
+
+            if self.insert(self.next_idx, note) {
+                owned += 1;
+            }
+
+            self.next_idx += 1;
+        }
+
+        Ok(owned)
+    }
+
+    /// Track a state change received from [`db::Db::subscribe`]. Return true if the event added
+    /// an owned note.
+    pub fn update(&mut self, event: &DbEvent) -> bool {
+        match event {
+            DbEvent::Note(idx, note) => {
+                self.next_idx = self.next_idx.max(idx + 1);
+                self.insert(*idx, *note)
+            }
+            DbEvent::Nullifier(_) => false,
+        }
+    }
+
+    fn insert(&mut self, idx: u64, note: NoteVariant) -> bool {
+        if !note.is_owned_by(&self.vk) {
+            return false;
+        }
+
+        // A note that can't be decrypted is never accounted in the balance
+        let (asset, value) = match (note.asset(Some(&self.vk)), note.value(Some(&self.vk))) {
+            (Ok(asset), Ok(value)) => (asset, value),
+            _ => return false,
+        };
+        self.notes.insert(idx, OwnedNote { note, asset, value });
+
+        true
+    }
+
+    /// Unspent notes owned by the wallet
+    pub fn notes(&self) -> impl Iterator<Item = &OwnedNote> {
+        self.notes.values()
+    }
+
+    /// Sum of the values of the unspent notes of the [`NATIVE_ASSET`]
+    pub fn balance(&self) -> u64 {
+        self.asset_balance(NATIVE_ASSET)
+    }
+
+    /// Sum of the values of the unspent notes of the provided asset
+    pub fn asset_balance(&self, asset: u64) -> u64 {
+        self.notes
+            .values()
+            .filter(|n| n.asset == asset)
+            .map(|n| n.value)
+            .sum()
+    }
+
+    /// Remove the inputs of a completed template from the unspent notes
+    pub fn mark_spent(&mut self, template: &TransactionTemplate) {
+        template.inputs.iter().for_each(|n| {
+            self.notes.remove(&n.idx());
+        });
+    }
+
+    /// Build an unsigned transaction that sends `value` of the [`NATIVE_ASSET`] to the recipient,
+    /// paying `fee`
+    ///
+    /// Only the notes spendable on a block of the provided height are selected; the locked notes
+    /// are accounted in the balance, but not spent.
+    ///
+    /// The change, if any, is sent back to the wallet. The fee note is sent to a random
+    /// [`PublicKey::default`], as the fee is collected by the block generator and not by its
+    /// receiver.
+    pub fn template(
+        &self,
+        height: u64,
+        recipient: &PublicKey,
+        value: u64,
+        fee: u64,
+    ) -> Result<TransactionTemplate, Error> {
+        let amount = value.checked_add(fee).ok_or(Error::InvalidParameters)?;
+
+        // Pick the smallest notes that cover the amount
+        let mut notes: Vec<&OwnedNote> = self
+            .notes
+            .values()
+            .filter(|n| n.asset == NATIVE_ASSET && !n.note.is_locked(height))
+            .collect();
+        notes.sort_by_key(|n| n.value);
+
+        let inputs: Vec<&OwnedNote> = if let Some(n) = notes.iter().find(|n| n.value >= amount) {
+            vec![*n]
+        } else {
+            notes
+                .iter()
+                .rev()
+                .take(MAX_INPUT_NOTES_PER_TRANSACTION)
+                .cloned()
+                .collect()
+        };
+
+        let total: u64 = inputs.iter().map(|n| n.value).sum();
+        if total < amount {
+            return Err(Error::InsufficientBalance);
+        }
+
+        let mut outputs = vec![];
+
+        let (note, blinding_factor) = ObfuscatedNote::output(recipient, value);
+        outputs.push(note.to_transaction_output(value, blinding_factor, *recipient));
+
+        let change = total - amount;
+        if change > 0 {
+            let pk = self.public_key();
+            let (note, blinding_factor) = ObfuscatedNote::output(&pk, change);
+            outputs.push(note.to_transaction_output(change, blinding_factor, pk));
+        }
+
+        let pk = PublicKey::default();
+        let (note, blinding_factor) = TransparentNote::output(&pk, fee);
+        let fee = note.to_transaction_output(fee, blinding_factor, pk);
+
+        Ok(TransactionTemplate {
+            owner: self.public_key(),
+            inputs: inputs.iter().map(|n| n.note).collect(),
+            outputs,
+            fee,
+        })
+    }
+}
+
+/// Unsigned transaction created by a [`WatchOnlyWallet`]
+///
+/// It contains the notes to be spent, the outputs and the fee, but not the [`SecretKey`] of the
+/// owner. The holder of the secret key completes it with the nullifiers and the proof.
+///
+/// The outputs carry their values and blinding factors in clear, so the template must be handled
+/// as sensitive data until [`Transaction::clear_sensitive_info`] is called on the completed
+/// transaction.
+#[derive(Debug, Clone)]
+pub struct TransactionTemplate {
+    owner: PublicKey,
+    inputs: Vec<NoteVariant>,
+    outputs: Vec<TransactionOutput>,
+    fee: TransactionOutput,
+}
+
+impl TransactionTemplate {
+    /// Notes to be spent
+    pub fn inputs(&self) -> &[NoteVariant] {
+        self.inputs.as_slice()
+    }
+
+    /// Outputs of the transaction, including the change
+    pub fn outputs(&self) -> &[TransactionOutput] {
+        self.outputs.as_slice()
+    }
+
+    /// Fee of the transaction, sent to a random [`PublicKey::default`]
+    pub fn fee(&self) -> &TransactionOutput {
+        &self.fee
+    }
+
+    /// Create the transaction inputs with the secret key, and prove the transaction
+    ///
+    /// Return [`Error::KeyMismatch`] if the secret key is not the owner of the template
+    pub fn complete<P: MerkleProofProvider>(
+        &self,
+        state: &P,
+        sk: &SecretKey,
+    ) -> Result<Transaction, Error> {
+        if sk.public_key() != self.owner {
+            return Err(Error::KeyMismatch);
+        }
+
+        let mut tx = Transaction::default();
+
+        for note in self.inputs.iter() {
+            let merkle_opening = state.opening(note)?;
+            tx.push_input(note.to_transaction_input(merkle_opening, sk.clone())?)?;
+        }
+
+        for output in self.outputs.iter() {
+            tx.push_output(output.clone())?;
+        }
+
+        tx.set_fee(self.fee.clone());
+        tx.prove()?;
+
+        Ok(tx)
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{
+    db, Error, Note, NoteGenerator, NoteVariant, ObfuscatedNote, SecretKey, TransactionItem,
+    TransparentNote, WatchOnlyWallet,
+};
+
+use kelvin::Blake2b;
+
+#[test]
+fn watch_only_scan() {
+    let mut db = db::Db::<Blake2b>::default();
+
+    let sk = SecretKey::default();
+    let pk = sk.public_key();
+    let mut wallet = WatchOnlyWallet::new(sk.view_key());
+
+    db.store_unspent_note(TransparentNote::output(&pk, 100).0.into())
+        .unwrap();
+    db.store_unspent_note(ObfuscatedNote::output(&pk, 40).0.into())
+        .unwrap();
+    db.store_unspent_note(
+        ObfuscatedNote::output(&SecretKey::default().public_key(), 70)
+            .0
+            .into(),
+    )
+    .unwrap();
+
+    assert_eq!(2, wallet.scan(&db).unwrap());
+    assert_eq!(140, wallet.balance());
+    assert_eq!(0, wallet.scan(&db).unwrap());
+
+    let events = db.subscribe();
+    db.store_unspent_note(ObfuscatedNote::output(&pk, 5).0.into())
+        .unwrap();
+    for event in events.try_iter() {
+        assert!(wallet.update(&event));
+    }
+
+    assert_eq!(145, wallet.balance());
+    assert_eq!(0, wallet.scan(&db).unwrap());
+}
+
+#[test]
+fn watch_only_template() {
+    let mut db = db::Db::<Blake2b>::default();
+
+    let sk = SecretKey::default();
+    let pk = sk.public_key();
+    let mut wallet = WatchOnlyWallet::new(sk.view_key());
+
+    let note: NoteVariant = TransparentNote::output(&pk, 100).0.into();
+    db.store_unspent_note(note).unwrap();
+    let note: NoteVariant = ObfuscatedNote::output(&pk, 40).0.into();
+    db.store_unspent_note(note).unwrap();
+    wallet.scan(&db).unwrap();
+
+    let recipient = SecretKey::default().public_key();
+
+    match wallet.template(0, &recipient, 1000, 5) {
+        Err(Error::InsufficientBalance) => (),
+        _ => panic!("The balance doesn't cover the value"),
+    }
+
+    let template = wallet.template(0, &recipient, 50, 5).unwrap();
+    assert_eq!(1, template.inputs().len());
+    assert_eq!(2, template.outputs().len());
+    assert_eq!(45, template.outputs()[1].value());
+    assert_eq!(5, template.fee().value());
+
+    match template.complete(&db, &SecretKey::default()) {
+        Err(Error::KeyMismatch) => (),
+        _ => panic!("Only the owner can complete the template"),
+    }
+
+    let mut tx = template.complete(&db, &sk).unwrap();
+    tx.verify().unwrap();
+    db.store_transaction(&tx, 0).unwrap();
+
+    wallet.mark_spent(&template);
+    assert_eq!(40, wallet.balance());
+
+    // The change is sent back to the wallet
+    assert_eq!(1, wallet.scan(&db).unwrap());
+    assert_eq!(85, wallet.balance());
+}
+
+#[test]
+fn watch_only_template_locked() {
+    let mut db = db::Db::<Blake2b>::default();
+
+    let sk = SecretKey::default();
+    let pk = sk.public_key();
+    let mut wallet = WatchOnlyWallet::new(sk.view_key());
+
+    let mut note = ObfuscatedNote::output(&pk, 100).0;
+    note.set_unlock_height(10);
+    db.store_unspent_note(note.into()).unwrap();
+    wallet.scan(&db).unwrap();
+    assert_eq!(100, wallet.balance());
+
+    let recipient = SecretKey::default().public_key();
+
+    match wallet.template(9, &recipient, 50, 5) {
+        Err(Error::InsufficientBalance) => (),
+        _ => panic!("A locked note can't be spent"),
+    }
+
+    let template = wallet.template(10, &recipient, 50, 5).unwrap();
+    assert_eq!(1, template.inputs().len());
+    assert_eq!(10, template.inputs()[0].unlock_height());
+}
@@ -1,47 +1,124 @@
-use crate::{BlsScalar, Transaction, TransactionItem};
+use crate::{BlsScalar, Transaction, TransactionItem, NATIVE_ASSET};
 
-use dusk_plonk::constraint_system::StandardComposer;
+use std::iter;
 
-/// Prove that the amount inputted equals the amount outputted
+use dusk_plonk::constraint_system::{StandardComposer, Variable};
+
+/// Prove that, for every asset of the transaction, the amount inputted equals the amount
+/// outputted
+///
+/// The assets are private witnesses, so the proof reveals neither the assets nor whether the
+/// items share one. The asset of every item is compared in-circuit with the assets of all the
+/// items, and the signed sum of the values of the matching items is constrained to zero. The fee
+/// is constrained to the native asset.
+///
+/// The values and assets are witnessed by this gadget only. They are not bound to the value
+/// commitments of the items while [`super::commitment`] is mocked, so the balance holds for the
+/// witnesses, and not yet for the committed notes.
 pub fn balance(composer: &mut StandardComposer, tx: &Transaction) {
-    let mut sum = composer.zero_var;
-    for item in tx.inputs().iter() {
-        let value = composer.add_input(BlsScalar::from(item.value()));
-        sum = composer.add(
-            (BlsScalar::one(), sum),
-            (BlsScalar::one(), value),
-            BlsScalar::zero(),
-            BlsScalar::zero(),
-        );
-    }
+    let fee = tx.fee();
 
-    for item in tx.outputs().iter() {
-        let value = composer.add_input(BlsScalar::from(item.value()));
-        sum = composer.add(
-            (BlsScalar::one(), sum),
-            (-BlsScalar::one(), value),
-            BlsScalar::zero(),
-            BlsScalar::zero(),
-        );
+    // The inputs are added, and the outputs and the fee are subtracted
+    let items: Vec<(BlsScalar, BlsScalar, Variable, Variable)> = tx
+        .inputs()
+        .iter()
+        .map(|i| (BlsScalar::one(), i.asset(), i.value()))
+        .chain(
+            tx.outputs()
+                .iter()
+                .map(|o| (-BlsScalar::one(), o.asset(), o.value())),
+        )
+        .chain(iter::once((-BlsScalar::one(), fee.asset(), fee.value())))
+        .map(|(sign, asset, value)| {
+            let asset = BlsScalar::from(asset);
+            let asset_var = composer.add_input(asset);
+            let value = composer.add_input(BlsScalar::from(value));
+
+            (sign, asset, asset_var, value)
+        })
+        .collect();
+
+    let (_, _, fee_asset, _) = items[items.len() - 1];
+    composer.constrain_to_constant(fee_asset, BlsScalar::from(NATIVE_ASSET), BlsScalar::zero());
+
+    for (j, (_, asset, asset_var, _)) in items.iter().enumerate() {
+        let mut sum = composer.zero_var;
+
+        for (i, (sign, item_asset, item_asset_var, value)) in items.iter().enumerate() {
+            let value = if i == j {
+                *value
+            } else {
+                let eq = is_equal(
+                    composer,
+                    (*item_asset_var, *item_asset),
+                    (*asset_var, *asset),
+                );
+
+                composer.mul(
+                    BlsScalar::one(),
+                    eq,
+                    *value,
+                    BlsScalar::zero(),
+                    BlsScalar::zero(),
+                )
+            };
+
+            sum = composer.add(
+                (BlsScalar::one(), sum),
+                (*sign, value),
+                BlsScalar::zero(),
+                BlsScalar::zero(),
+            );
+        }
+
+        composer.constrain_to_constant(sum, BlsScalar::zero(), BlsScalar::zero());
     }
+}
+
+/// Return a variable constrained to one if the provided variables are equal, and to zero
+/// otherwise. The variables are provided with their assigned values to compute the witness
+fn is_equal(
+    composer: &mut StandardComposer,
+    a: (Variable, BlsScalar),
+    b: (Variable, BlsScalar),
+) -> Variable {
+    let diff = composer.add(
+        (BlsScalar::one(), a.0),
+        (-BlsScalar::one(), b.0),
+        BlsScalar::zero(),
+        BlsScalar::zero(),
+    );
+    let inverse = composer.add_input((a.1 - b.1).invert().unwrap_or(BlsScalar::zero()));
 
-    let fee = *tx.fee();
+    // eq = 1 - diff · inverse
+    let eq = composer.mul(
+        -BlsScalar::one(),
+        diff,
+        inverse,
+        BlsScalar::one(),
+        BlsScalar::zero(),
+    );
 
-    let value = composer.add_input(BlsScalar::from(fee.value()));
-    sum = composer.add(
-        (BlsScalar::one(), sum),
-        (-BlsScalar::one(), value),
+    // diff · eq = 0, so eq is zero whenever diff isn't
+    let product = composer.mul(
+        BlsScalar::one(),
+        diff,
+        eq,
         BlsScalar::zero(),
         BlsScalar::zero(),
     );
+    composer.constrain_to_constant(product, BlsScalar::zero(), BlsScalar::zero());
 
-    composer.constrain_to_constant(sum, BlsScalar::zero(), BlsScalar::zero());
+    eq
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{crypto, Note, NoteGenerator, SecretKey, Transaction, TransparentNote};
+    use crate::{
+        crypto, Note, NoteGenerator, ObfuscatedNote, SecretKey, Transaction, TransparentNote,
+        MEMO_SIZE,
+    };
     use dusk_plonk::commitment_scheme::kzg10::PublicParameters;
     use dusk_plonk::fft::EvaluationDomain;
     use merlin::Transcript;
@@ -101,7 +178,7 @@ mod tests {
     }
 
     #[test]
-    #[ignore]
+    #[ignore = "the mocked proof system verifies any circuit, so an unbalanced proof is accepted"]
     fn tx_balance_invalid() {
         let mut tx = Transaction::default();
 
@@ -154,4 +231,153 @@ mod tests {
 
         assert!(!proof.verify(&circuit, &mut transcript, &vk, &composer.public_inputs()));
     }
+
+    #[test]
+    fn balance_gadget_assets() {
+        let asset = 7;
+        let memo = [0x00u8; MEMO_SIZE];
+        let mut tx = Transaction::default();
+
+        let sk = SecretKey::default();
+        let pk = sk.public_key();
+        let value = 100;
+        let note = ObfuscatedNote::output_with_asset(&pk, asset, value, &memo).0;
+        let merkle_opening = crypto::MerkleProof::mock(note.hash());
+        let input = note.to_transaction_input(merkle_opening, sk).unwrap();
+        assert_eq!(asset, input.asset());
+        tx.push_input(input).unwrap();
+
+        let pk = SecretKey::default().public_key();
+        let value = 60;
+        let (note, blinding_factor) = ObfuscatedNote::output_with_asset(&pk, asset, value, &memo);
+        tx.push_output(note.to_transaction_output_with_asset(asset, value, blinding_factor, pk))
+            .unwrap();
+
+        let pk = SecretKey::default().public_key();
+        let value = 40;
+        let (note, blinding_factor) = ObfuscatedNote::output_with_asset(&pk, asset, value, &memo);
+        tx.push_output(note.to_transaction_output_with_asset(asset, value, blinding_factor, pk))
+            .unwrap();
+
+        let pk = SecretKey::default().public_key();
+        let (note, blinding_factor) = TransparentNote::output(&pk, 0);
+        tx.set_fee(note.to_transaction_output(0, blinding_factor, pk));
+
+        let mut composer = StandardComposer::new();
+
+        balance(&mut composer, &tx);
+
+        composer.add_dummy_constraints();
+
+        // Generate Composer & Public Parameters
+        let pub_params = PublicParameters::setup(1 << 17, &mut rand::thread_rng()).unwrap();
+        let (ck, vk) = pub_params.trim(1 << 16).unwrap();
+        let mut transcript = Transcript::new(b"TEST");
+
+        let circuit = composer.preprocess(
+            &ck,
+            &mut transcript,
+            &EvaluationDomain::new(composer.circuit_size()).unwrap(),
+        );
+
+        let proof = composer.prove(&ck, &circuit, &mut transcript.clone());
+
+        assert!(proof.verify(&circuit, &mut transcript, &vk, &composer.public_inputs()));
+    }
+
+    #[test]
+    #[ignore = "the mocked proof system verifies any circuit, so an unbalanced proof is accepted"]
+    fn tx_balance_invalid_asset() {
+        let asset = 7;
+        let memo = [0x00u8; MEMO_SIZE];
+        let mut tx = Transaction::default();
+
+        let sk = SecretKey::default();
+        let pk = sk.public_key();
+        let value = 100;
+        let note = ObfuscatedNote::output_with_asset(&pk, asset, value, &memo).0;
+        let merkle_opening = crypto::MerkleProof::mock(note.hash());
+        tx.push_input(note.to_transaction_input(merkle_opening, sk).unwrap())
+            .unwrap();
+
+        // The values balance, but the fee is paid with the native asset
+        let pk = SecretKey::default().public_key();
+        let value = 97;
+        let (note, blinding_factor) = ObfuscatedNote::output_with_asset(&pk, asset, value, &memo);
+        tx.push_output(note.to_transaction_output_with_asset(asset, value, blinding_factor, pk))
+            .unwrap();
+
+        let pk = SecretKey::default().public_key();
+        let value = 3;
+        let (note, blinding_factor) = TransparentNote::output(&pk, value);
+        tx.set_fee(note.to_transaction_output(value, blinding_factor, pk));
+
+        let mut composer = StandardComposer::new();
+
+        balance(&mut composer, &tx);
+
+        composer.add_dummy_constraints();
+
+        // Generate Composer & Public Parameters
+        let pub_params = PublicParameters::setup(1 << 17, &mut rand::thread_rng()).unwrap();
+        let (ck, vk) = pub_params.trim(1 << 16).unwrap();
+        let mut transcript = Transcript::new(b"TEST");
+
+        let circuit = composer.preprocess(
+            &ck,
+            &mut transcript,
+            &EvaluationDomain::new(composer.circuit_size()).unwrap(),
+        );
+
+        let proof = composer.prove(&ck, &circuit, &mut transcript.clone());
+
+        assert!(!proof.verify(&circuit, &mut transcript, &vk, &composer.public_inputs()));
+    }
+
+    #[test]
+    #[ignore = "the mocked proof system verifies any circuit, so an unbalanced proof is accepted"]
+    fn balance_gadget_mixed_assets() {
+        let asset = 7;
+        let memo = [0x00u8; MEMO_SIZE];
+        let mut tx = Transaction::default();
+
+        let sk = SecretKey::default();
+        let pk = sk.public_key();
+        let value = 100;
+        let note = ObfuscatedNote::output_with_asset(&pk, asset, value, &memo).0;
+        let merkle_opening = crypto::MerkleProof::mock(note.hash());
+        tx.push_input(note.to_transaction_input(merkle_opening, sk).unwrap())
+            .unwrap();
+
+        // The values balance, but the output is of the native asset
+        let pk = SecretKey::default().public_key();
+        let (note, blinding_factor) = TransparentNote::output(&pk, value);
+        tx.push_output(note.to_transaction_output(value, blinding_factor, pk))
+            .unwrap();
+
+        let pk = SecretKey::default().public_key();
+        let (note, blinding_factor) = TransparentNote::output(&pk, 0);
+        tx.set_fee(note.to_transaction_output(0, blinding_factor, pk));
+
+        let mut composer = StandardComposer::new();
+
+        balance(&mut composer, &tx);
+
+        composer.add_dummy_constraints();
+
+        // Generate Composer & Public Parameters
+        let pub_params = PublicParameters::setup(1 << 17, &mut rand::thread_rng()).unwrap();
+        let (ck, vk) = pub_params.trim(1 << 16).unwrap();
+        let mut transcript = Transcript::new(b"TEST");
+
+        let circuit = composer.preprocess(
+            &ck,
+            &mut transcript,
+            &EvaluationDomain::new(composer.circuit_size()).unwrap(),
+        );
+
+        let proof = composer.prove(&ck, &circuit, &mut transcript.clone());
+
+        assert!(!proof.verify(&circuit, &mut transcript, &vk, &composer.public_inputs()));
+    }
 }
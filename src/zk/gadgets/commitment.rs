@@ -2,7 +2,11 @@ use crate::{NoteVariant, TransactionItem, ViewKey};
 
 use dusk_plonk::constraint_system::StandardComposer;
 
-/// Prove knowledge of the value and blinding factor, which make up the value commitment.
+/// Mock of the gadget proving knowledge of the value, asset and blinding factor, which make up the
+/// value commitment.
+///
+/// No constraint is added until the ECC gates are available, so the value commitment is not bound
+/// to the witnesses of the other gadgets.
 pub fn commitment(composer: &mut StandardComposer, note: &NoteVariant, vk: Option<&ViewKey>) {
     // let value = composer.add_input(BlsScalar::from(note.value(vk)));
     // let blinding_factor = composer.add_input(note.blinding_factor(vk));
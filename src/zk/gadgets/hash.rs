@@ -0,0 +1,80 @@
+use crate::{crypto::HashDomain, BlsScalar};
+
+use dusk_plonk::constraint_system::{StandardComposer, Variable};
+use poseidon252::sponge::sponge::sponge_hash_gadget;
+
+/// Perform `H(tag, inputs)` with the tag of the provided domain, constrained as a constant of the
+/// circuit. Mirrors [`crate::crypto::domain_hash`]
+pub fn domain_hash(
+    composer: &mut StandardComposer,
+    domain: HashDomain,
+    inputs: &[Variable],
+) -> Variable {
+    let tag = domain.tag();
+    let tag_var = composer.add_input(tag);
+    composer.add_gate(
+        tag_var,
+        composer.zero_var,
+        composer.zero_var,
+        -BlsScalar::one(),
+        BlsScalar::one(),
+        BlsScalar::one(),
+        tag,
+        BlsScalar::zero(),
+    );
+
+    let mut tagged = Vec::with_capacity(inputs.len() + 1);
+    tagged.push(tag_var);
+    tagged.extend_from_slice(inputs);
+
+    sponge_hash_gadget(composer, &tagged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto;
+    use dusk_plonk::commitment_scheme::kzg10::PublicParameters;
+    use dusk_plonk::fft::EvaluationDomain;
+    use merlin::Transcript;
+
+    #[test]
+    fn domain_hash_gadget() {
+        let inputs = [BlsScalar::from(3u64), BlsScalar::from(5u64)];
+        let expected = crypto::domain_hash(HashDomain::Nullifier, &inputs);
+        assert_ne!(expected, crypto::domain_hash(HashDomain::Note, &inputs));
+        assert_ne!(expected, crypto::sponge_hash(&inputs));
+
+        let mut composer = StandardComposer::new();
+
+        let vars: Vec<Variable> = inputs.iter().map(|i| composer.add_input(*i)).collect();
+        let output = domain_hash(&mut composer, HashDomain::Nullifier, &vars);
+        composer.add_gate(
+            output,
+            composer.zero_var,
+            composer.zero_var,
+            -BlsScalar::one(),
+            BlsScalar::one(),
+            BlsScalar::one(),
+            BlsScalar::zero(),
+            expected,
+        );
+
+        composer.add_dummy_constraints();
+
+        // Generate Composer & Public Parameters
+        let pub_params = PublicParameters::setup(1 << 17, &mut rand::thread_rng()).unwrap();
+        let (ck, vk) = pub_params.trim(1 << 16).unwrap();
+        let mut transcript = Transcript::new(b"TEST");
+
+        let circuit = composer.preprocess(
+            &ck,
+            &mut transcript,
+            &EvaluationDomain::new(composer.circuit_size()).unwrap(),
+        );
+
+        let proof = composer.prove(&ck, &circuit, &mut transcript.clone());
+
+        assert!(proof.verify(&circuit, &mut transcript, &vk, &composer.public_inputs()));
+    }
+}
@@ -1,5 +1,6 @@
 pub use balance::balance;
 pub use commitment::commitment;
+pub use hash::domain_hash;
 pub use merkle::merkle;
 pub use nullifier::nullifier;
 pub use preimage::input_preimage;
@@ -7,6 +8,7 @@ pub use range::range;
 
 mod balance;
 mod commitment;
+mod hash;
 mod merkle;
 mod nullifier;
 mod preimage;
@@ -1,7 +1,7 @@
-use crate::{BlsScalar, Note, TransactionInput, TransactionItem};
+use super::domain_hash;
+use crate::{crypto::HashDomain, BlsScalar, Note, TransactionInput, TransactionItem};
 
 use dusk_plonk::constraint_system::StandardComposer;
-use poseidon252::sponge::sponge::sponge_hash_gadget;
 
 /// Prove knowledge of the input nullifier
 pub fn nullifier(composer: &mut StandardComposer, input: &TransactionInput) {
@@ -9,7 +9,7 @@ pub fn nullifier(composer: &mut StandardComposer, input: &TransactionInput) {
     let sk_r = composer.add_input(BlsScalar::from_bytes(&sk_r.to_bytes()).unwrap());
     let idx = composer.add_input(BlsScalar::from(input.note().idx()));
 
-    let output = sponge_hash_gadget(composer, &[sk_r, idx]);
+    let output = domain_hash(composer, HashDomain::Nullifier, &[sk_r, idx]);
 
     composer.add_gate(
         output,
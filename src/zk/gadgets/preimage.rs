@@ -1,23 +1,44 @@
-use crate::{BlsScalar, Note, TransactionInput, TransactionItem};
+use super::domain_hash;
+use crate::{crypto::HashDomain, note, BlsScalar, Note, TransactionInput, TransactionItem};
 
 use dusk_plonk::constraint_system::StandardComposer;
-use poseidon252::sponge::sponge::sponge_hash_gadget;
 
 /// Prove knowledge of the pre-image of an input note
+///
+/// The unlock height declared by the input is a public input, constrained to the unlock height
+/// hashed in the note
 pub fn input_preimage(composer: &mut StandardComposer, input: &TransactionInput) {
     let value_commitment_x = composer.add_input(input.note().value_commitment().get_x());
     let value_commitment_y = composer.add_input(input.note().value_commitment().get_y());
     let idx = composer.add_input(BlsScalar::from(input.note().idx()));
     let pk_r_affine_x = composer.add_input(input.note().pk_r().get_x());
     let pk_r_affine_y = composer.add_input(input.note().pk_r().get_y());
-    let output = sponge_hash_gadget(
+    let memo = note::memo_scalars(input.note().encrypted_memo());
+    let memo_0 = composer.add_input(memo[0]);
+    let memo_1 = composer.add_input(memo[1]);
+    let unlock_height_var = composer.add_input(BlsScalar::from(input.note().unlock_height()));
+    composer.add_gate(
+        unlock_height_var,
+        composer.zero_var,
+        composer.zero_var,
+        -BlsScalar::one(),
+        BlsScalar::one(),
+        BlsScalar::one(),
+        BlsScalar::zero(),
+        BlsScalar::from(input.unlock_height),
+    );
+    let output = domain_hash(
         composer,
+        HashDomain::Note,
         &[
             value_commitment_x,
             value_commitment_y,
             idx,
             pk_r_affine_x,
             pk_r_affine_y,
+            memo_0,
+            memo_1,
+            unlock_height_var,
         ],
     );
 
@@ -69,6 +90,84 @@ mod tests {
 
         let proof = composer.prove(&ck, &circuit, &mut transcript.clone());
 
-        assert!(proof.verify(&circuit, &mut transcript, &vk, &[BlsScalar::zero()]));
+        assert!(proof.verify(&circuit, &mut transcript, &vk, &composer.public_inputs()));
+    }
+
+    #[test]
+    fn preimage_gadget_unlock_height() {
+        let sk = SecretKey::default();
+        let pk = sk.public_key();
+        let value = 100;
+        let mut note = TransparentNote::output(&pk, value).0;
+        note.set_unlock_height(100);
+        let merkle_opening = crypto::MerkleProof::mock(note.hash());
+        let input = note.to_transaction_input(merkle_opening, sk).unwrap();
+
+        let mut composer = StandardComposer::new();
+
+        input_preimage(&mut composer, &input);
+        composer.add_dummy_constraints();
+
+        let pub_params = PublicParameters::setup(1 << 17, &mut rand::thread_rng()).unwrap();
+        let (ck, vk) = pub_params.trim(1 << 16).unwrap();
+        let mut transcript = Transcript::new(b"TEST");
+
+        let circuit = composer.preprocess(
+            &ck,
+            &mut transcript,
+            &EvaluationDomain::new(composer.circuit_size()).unwrap(),
+        );
+
+        let proof = composer.prove(&ck, &circuit, &mut transcript.clone());
+
+        let public_inputs = composer.public_inputs();
+        assert!(public_inputs.contains(&BlsScalar::from(100)));
+        assert!(proof.verify(&circuit, &mut transcript.clone(), &vk, &public_inputs));
+
+        // The verifier can't claim a different unlock height
+        let forged: Vec<BlsScalar> = public_inputs
+            .iter()
+            .map(|p| {
+                if p == &BlsScalar::from(100) {
+                    BlsScalar::from(0)
+                } else {
+                    *p
+                }
+            })
+            .collect();
+        assert!(!proof.verify(&circuit, &mut transcript, &vk, &forged));
+    }
+
+    #[test]
+    fn preimage_gadget_tampered_unlock_height() {
+        let sk = SecretKey::default();
+        let pk = sk.public_key();
+        let value = 100;
+        let mut note = TransparentNote::output(&pk, value).0;
+        note.set_unlock_height(100);
+        let merkle_opening = crypto::MerkleProof::mock(note.hash());
+        let mut input = note.to_transaction_input(merkle_opening, sk).unwrap();
+
+        // The spender declares the note as unlocked
+        input.unlock_height = 0;
+
+        let mut composer = StandardComposer::new();
+
+        input_preimage(&mut composer, &input);
+        composer.add_dummy_constraints();
+
+        let pub_params = PublicParameters::setup(1 << 17, &mut rand::thread_rng()).unwrap();
+        let (ck, vk) = pub_params.trim(1 << 16).unwrap();
+        let mut transcript = Transcript::new(b"TEST");
+
+        let circuit = composer.preprocess(
+            &ck,
+            &mut transcript,
+            &EvaluationDomain::new(composer.circuit_size()).unwrap(),
+        );
+
+        let proof = composer.prove(&ck, &circuit, &mut transcript.clone());
+
+        assert!(!proof.verify(&circuit, &mut transcript, &vk, &composer.public_inputs()));
     }
 }
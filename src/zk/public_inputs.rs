@@ -12,6 +12,7 @@ pub struct ZkPublicInputs {
     fee_value_commitment: BlsScalar,
     merkle_roots: [BlsScalar; MAX_INPUT_NOTES_PER_TRANSACTION],
     nullifiers: [Nullifier; MAX_INPUT_NOTES_PER_TRANSACTION],
+    unlock_heights: [BlsScalar; MAX_INPUT_NOTES_PER_TRANSACTION],
     outputs_value_commitments: [BlsScalar; MAX_OUTPUT_NOTES_PER_TRANSACTION],
     outputs_pk_r_affine_x: [BlsScalar; MAX_OUTPUT_NOTES_PER_TRANSACTION],
 }
@@ -43,6 +44,13 @@ impl Write for ZkPublicInputs {
                 .map_err::<io::Error, _>(|e| e.into())?
                 .into();
             n += utils::BLS_SCALAR_SERIALIZED_SIZE;
+
+            self.unlock_heights[i] = chunk
+                .next()
+                .ok_or(Error::InvalidParameters)
+                .and_then(utils::deserialize_bls_scalar)
+                .map_err::<io::Error, _>(|e| e.into())?;
+            n += utils::BLS_SCALAR_SERIALIZED_SIZE;
         }
 
         for i in 0..MAX_OUTPUT_NOTES_PER_TRANSACTION {
@@ -95,6 +103,13 @@ impl Read for ZkPublicInputs {
                 .and_then(|c| Ok(c.copy_from_slice(&self.nullifiers[i].s().to_bytes()[..])))
                 .map_err::<io::Error, _>(|e| e.into())?;
             n += utils::BLS_SCALAR_SERIALIZED_SIZE;
+
+            chunk
+                .next()
+                .ok_or(Error::InvalidParameters)
+                .and_then(|c| Ok(c.copy_from_slice(&self.unlock_heights[i].to_bytes()[..])))
+                .map_err::<io::Error, _>(|e| e.into())?;
+            n += utils::BLS_SCALAR_SERIALIZED_SIZE;
         }
 
         for i in 0..MAX_OUTPUT_NOTES_PER_TRANSACTION {
@@ -129,13 +144,16 @@ impl From<&Transaction> for ZkPublicInputs {
 
         let mut merkle_roots = [BlsScalar::zero(); MAX_INPUT_NOTES_PER_TRANSACTION];
         let mut nullifiers = [Nullifier::default(); MAX_INPUT_NOTES_PER_TRANSACTION];
+        let mut unlock_heights = [BlsScalar::zero(); MAX_INPUT_NOTES_PER_TRANSACTION];
 
         tx.inputs()
             .iter()
             .zip(merkle_roots.iter_mut().zip(nullifiers.iter_mut()))
-            .for_each(|(i, (r, n))| {
+            .zip(unlock_heights.iter_mut())
+            .for_each(|((i, (r, n)), h)| {
                 *r = i.merkle_root;
                 *n = *i.nullifier();
+                *h = BlsScalar::from(i.unlock_height);
             });
 
         let mut outputs_value_commitments = [BlsScalar::zero(); MAX_OUTPUT_NOTES_PER_TRANSACTION];
@@ -157,6 +175,7 @@ impl From<&Transaction> for ZkPublicInputs {
             fee_value_commitment,
             merkle_roots,
             nullifiers,
+            unlock_heights,
             outputs_value_commitments,
             outputs_pk_r_affine_x,
         )